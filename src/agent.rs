@@ -0,0 +1,375 @@
+//! Players driven by code, for bot and machine learning competitions. An `Agent` is
+//! shown the match as an `Observation` every physics tick and answers with an
+//! `AgentAction`, which moves its player the same way a controller would. Put one on
+//! either side with `--p1=agent:<name>` or `--p2=agent:<name>`, see `AgentKind` for
+//! the names. A match without a human keeps running when the window loses focus, and
+//! `--headless` plays it with no window or rendering at all.
+//!
+//! The observation is mirrored so that every agent sees itself on the left side of
+//! the court facing right, at the net:
+//!
+//! - x is measured from the net toward the opponent, scaled so the agent's own
+//!   baseline is at -1 and the opponent's at 1.
+//! - y is the height above the court surface, on the same scale.
+//! - Velocities are in those units per second, positive y up.
+//! - Spin is in radians per second, positive is counterclockwise in the mirrored
+//!   view, so a ball flying toward the opponent with topspin has negative spin.
+//! - Curve is the push a curve shot puts across the ball's flight, in units per
+//!   second squared. It is zero for a shot hit without curve and fades out in flight.
+
+use bevy::prelude::*;
+
+use crate::{
+    controller::PlayerInput,
+    court::{Court, CourtSide},
+    racket::facing,
+    scoring::MatchState,
+    serve::ServeState,
+    stamina::Stamina,
+    Ball, BallState, Curve, Movement, Player, PlayerId, Spin,
+};
+
+// Physics ticks the random agent keeps doing the same thing for
+const RANDOM_HOLD_TICKS: u32 = 15;
+
+pub type BallObservationQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Transform,
+        &'static Movement,
+        &'static Spin,
+        &'static Curve,
+        &'static BallState,
+    ),
+    With<Ball>,
+>;
+pub type PlayerObservationQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static PlayerId,
+        &'static Transform,
+        &'static Movement,
+        &'static Stamina,
+    ),
+    With<Player>,
+>;
+
+/// A player as an agent sees it, in the mirrored coordinates described above.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlayerObservation {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub facing_net: bool,
+    pub on_ground: bool,
+    /// From 1.0 when fresh down to 0.0 when exhausted.
+    pub stamina: f32,
+}
+
+impl PlayerObservation {
+    fn features(&self) -> [f32; 7] {
+        [
+            self.position.x,
+            self.position.y,
+            self.velocity.x,
+            self.velocity.y,
+            self.facing_net as u8 as f32,
+            self.on_ground as u8 as f32,
+            self.stamina,
+        ]
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Observation {
+    pub ball_position: Vec2,
+    pub ball_velocity: Vec2,
+    pub ball_spin: f32,
+    pub ball_curve: Vec2,
+    /// The ball is in the air and can be hit, from the toss until the point is over.
+    pub ball_in_play: bool,
+    /// The agent serves the next point and the ball is waiting to be tossed.
+    pub serving: bool,
+    pub own: PlayerObservation,
+    pub opponent: PlayerObservation,
+}
+
+impl Observation {
+    pub const FEATURES: usize = 23;
+
+    /// The observation as a flat vector, in field order with flags as 0.0 or 1.0.
+    pub fn features(&self) -> [f32; Self::FEATURES] {
+        let mut features = [0.0; Self::FEATURES];
+        features[..9].copy_from_slice(&[
+            self.ball_position.x,
+            self.ball_position.y,
+            self.ball_velocity.x,
+            self.ball_velocity.y,
+            self.ball_spin,
+            self.ball_curve.x,
+            self.ball_curve.y,
+            self.ball_in_play as u8 as f32,
+            self.serving as u8 as f32,
+        ]);
+        features[9..16].copy_from_slice(&self.own.features());
+        features[16..].copy_from_slice(&self.opponent.features());
+        features
+    }
+}
+
+/// What the agent does this tick. `jump` is held like the key, `swing`, `dash` and
+/// `dive` are presses that only act on the tick they are set. `lob`, `drop` and
+/// `curve` pick the shot and have to be held through the swing.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AgentAction {
+    /// Toward the net.
+    pub forward: bool,
+    pub back: bool,
+    pub jump: bool,
+    pub swing: bool,
+    pub lob: bool,
+    pub drop: bool,
+    pub dash: bool,
+    pub dive: bool,
+    pub curve: bool,
+}
+
+impl AgentAction {
+    /// What a controller asked the player on `side` to do, as an agent would have.
+    pub fn from_input(input: &PlayerInput, side: CourtSide) -> Self {
+        let (forward, back) = match side {
+            CourtSide::Left => (input.right, input.left),
+            CourtSide::Right => (input.left, input.right),
+        };
+        Self {
+            forward,
+            back,
+            jump: input.jump,
+            swing: input.swing_pressed,
+            lob: input.lob,
+            drop: input.drop,
+            dash: input.dash_pressed,
+            dive: input.dive_pressed,
+            curve: input.curve,
+        }
+    }
+
+    /// One bit per field, in field order from the lowest bit.
+    pub fn bits(&self) -> u16 {
+        [
+            self.forward,
+            self.back,
+            self.jump,
+            self.swing,
+            self.lob,
+            self.drop,
+            self.dash,
+            self.dive,
+            self.curve,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (bit, &set)| bits | ((set as u16) << bit))
+    }
+}
+
+pub trait Agent: Send + Sync {
+    fn act(&mut self, observation: &Observation) -> AgentAction;
+}
+
+/// Does something random, changing its mind a few times a second. A starting point
+/// for writing agents and a baseline to beat.
+#[derive(Default)]
+pub struct RandomAgent {
+    action: AgentAction,
+    ticks_left: u32,
+}
+
+impl Agent for RandomAgent {
+    fn act(&mut self, _observation: &Observation) -> AgentAction {
+        if self.ticks_left > 0 {
+            self.ticks_left -= 1;
+            // Presses only last the tick they were chosen on
+            return AgentAction {
+                swing: false,
+                dash: false,
+                dive: false,
+                ..self.action
+            };
+        }
+        self.ticks_left = RANDOM_HOLD_TICKS;
+        self.action = AgentAction {
+            forward: rand::random(),
+            back: rand::random(),
+            jump: rand::random::<f32>() < 0.2,
+            swing: rand::random(),
+            lob: rand::random::<f32>() < 0.2,
+            drop: rand::random::<f32>() < 0.2,
+            dash: rand::random::<f32>() < 0.1,
+            dive: rand::random::<f32>() < 0.05,
+            curve: rand::random::<f32>() < 0.2,
+        };
+        self.action
+    }
+}
+
+/// The agents that can be picked from the command line.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AgentKind {
+    Random,
+}
+
+impl AgentKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "random" => Some(AgentKind::Random),
+            _ => None,
+        }
+    }
+
+    pub fn create(self) -> Box<dyn Agent> {
+        match self {
+            AgentKind::Random => Box::<RandomAgent>::default(),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct AgentController {
+    agent: Box<dyn Agent>,
+    // Whether jump was held last tick, to turn holding into presses
+    jump_held: bool,
+}
+
+impl AgentController {
+    pub fn new(agent: Box<dyn Agent>) -> Self {
+        Self {
+            agent,
+            jump_held: false,
+        }
+    }
+}
+
+// Converts between world coordinates and the mirrored view of the player on `side`
+struct View<'a> {
+    court: &'a Court,
+    mirror: f32,
+}
+
+impl<'a> View<'a> {
+    fn new(court: &'a Court, side: CourtSide) -> Self {
+        let mirror = match side {
+            CourtSide::Left => 1.0,
+            CourtSide::Right => -1.0,
+        };
+        Self { court, mirror }
+    }
+
+    fn position(&self, translation: Vec3) -> Vec2 {
+        Vec2::new(
+            (translation.x - self.court.net_x) * self.mirror,
+            translation.y - self.court.ground_y,
+        ) / self.court.baseline
+    }
+
+    // Movement treats positive y as down
+    fn velocity(&self, velocity: Vec2) -> Vec2 {
+        Vec2::new(velocity.x * self.mirror, -velocity.y) / self.court.baseline
+    }
+
+    fn player(
+        &self,
+        transform: &Transform,
+        movement: &Movement,
+        stamina: &Stamina,
+    ) -> PlayerObservation {
+        PlayerObservation {
+            position: self.position(transform.translation),
+            velocity: self.velocity(movement.velocity),
+            facing_net: facing(transform) == self.mirror,
+            on_ground: movement.on_ground,
+            stamina: stamina.level(),
+        }
+    }
+}
+
+pub fn player_side(id: &PlayerId) -> CourtSide {
+    if id.0 == 0 {
+        CourtSide::Left
+    } else {
+        CourtSide::Right
+    }
+}
+
+/// The match as the player `id` sees it.
+pub fn observe(
+    court: &Court,
+    match_state: &MatchState,
+    serve_state: &ServeState,
+    ball_query: &BallObservationQuery,
+    player_query: &PlayerObservationQuery,
+    id: &PlayerId,
+) -> Observation {
+    let (ball_transform, ball_movement, ball_spin, ball_curve, ball_state) = ball_query.single();
+    let side = player_side(id);
+    let view = View::new(court, side);
+    let mut own = PlayerObservation::default();
+    let mut opponent = PlayerObservation::default();
+    for (player, transform, movement, stamina) in player_query {
+        let observation = view.player(transform, movement, stamina);
+        if player == id {
+            own = observation;
+        } else {
+            opponent = observation;
+        }
+    }
+    Observation {
+        ball_position: view.position(ball_transform.translation),
+        ball_velocity: view.velocity(ball_movement.velocity),
+        ball_spin: ball_spin.0 * view.mirror,
+        // An acceleration, but it mirrors and scales like a velocity
+        ball_curve: view.velocity(ball_curve.0),
+        ball_in_play: ball_state.in_flight(),
+        serving: *serve_state == ServeState::Ready
+            && *ball_state == BallState::Held
+            && match_state.server() == side,
+        own,
+        opponent,
+    }
+}
+
+pub fn agent_controller_system(
+    court: Res<Court>,
+    match_state: Res<MatchState>,
+    serve_state: Res<ServeState>,
+    ball_query: BallObservationQuery,
+    player_query: PlayerObservationQuery,
+    mut query: Query<(&PlayerId, &mut AgentController, &mut PlayerInput)>,
+) {
+    for (id, mut controller, mut input) in &mut query {
+        let observation = observe(
+            &court,
+            &match_state,
+            &serve_state,
+            &ball_query,
+            &player_query,
+            id,
+        );
+        let action = controller.agent.act(&observation);
+        let (toward_net, away) = (action.forward, action.back);
+        (input.left, input.right) = match player_side(id) {
+            CourtSide::Left => (away, toward_net),
+            CourtSide::Right => (toward_net, away),
+        };
+        input.jump = action.jump;
+        input.jump_pressed = action.jump && !controller.jump_held;
+        controller.jump_held = action.jump;
+        input.swing_pressed = action.swing;
+        input.lob = action.lob;
+        input.drop = action.drop;
+        input.dash_pressed = action.dash;
+        input.dive_pressed = action.dive;
+        input.curve = action.curve;
+    }
+}
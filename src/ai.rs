@@ -0,0 +1,220 @@
+//! Computer opponent. It reads the predicted flight of the ball, walks to where the
+//! ball will drop to racket height on its side of the net and swings as it arrives.
+//! Otherwise it waits at its service line.
+//!
+//! The same positioning drives the auto-move assist, which walks a human player to
+//! the ball and leaves only the swing to them, so one switch is enough to play.
+
+use bevy::prelude::*;
+
+use crate::{
+    controller::PlayerInput,
+    court::Court,
+    match_config::MatchConfig,
+    racket::{facing, racket_center},
+    scoring::MatchState,
+    serve::ServeState,
+    trajectory::{predict_ball_path, MAX_PREDICTION_TICKS},
+    Ball, BallState, Curve, Movement, Player, Size, Spin, RACKET_SIZE,
+};
+
+// Close enough to the target to stop running, keeps the AI from jittering around it
+const POSITION_TOLERANCE: f32 = 4.0;
+// Ticks of lookahead inside which the AI starts its swing
+const SWING_LOOKAHEAD_TICKS: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum AiDifficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl AiDifficulty {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(AiDifficulty::Easy),
+            "normal" => Some(AiDifficulty::Normal),
+            "hard" => Some(AiDifficulty::Hard),
+            _ => None,
+        }
+    }
+
+    // How far along the ball's flight the AI reads, weaker AIs react later
+    fn read_ahead_ticks(self) -> usize {
+        match self {
+            AiDifficulty::Easy => 30,
+            AiDifficulty::Normal => 60,
+            AiDifficulty::Hard => MAX_PREDICTION_TICKS,
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct AiController {
+    pub difficulty: AiDifficulty,
+}
+
+/// Assist that moves a human player into position on its own, see `--auto-move`.
+#[derive(Component)]
+pub struct AutoMove;
+
+pub type BallQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Transform,
+        &'static Movement,
+        &'static Spin,
+        &'static Curve,
+        &'static Size,
+        &'static BallState,
+    ),
+    With<Ball>,
+>;
+
+// Where the racket should meet the ball, if the ball is coming to this side
+fn intercept(path: &[Vec2], court: &Court, own_x: f32, racket_y: f32) -> Option<(usize, Vec2)> {
+    let side = court.side_of(own_x);
+    path.windows(2)
+        .enumerate()
+        .find(|(_, step)| {
+            court.side_of(step[1].x) == side && step[1].y < step[0].y && step[1].y <= racket_y
+        })
+        .map(|(tick, step)| (tick + 1, step[1]))
+}
+
+struct Positioning {
+    // From the player to where it should stand
+    offset: f32,
+    toward_net: f32,
+    interception: Option<(usize, Vec2)>,
+}
+
+// Where the player should stand, reading the ball's flight `read_ahead_ticks` ahead
+fn plan_position(
+    court: &Court,
+    match_config: &MatchConfig,
+    ball_query: &BallQuery,
+    transform: &Transform,
+    size: &Size,
+    read_ahead_ticks: usize,
+) -> Positioning {
+    let (ball_transform, ball_movement, ball_spin, ball_curve, ball_size, ball_state) =
+        ball_query.single();
+    let own_x = transform.translation.x;
+    let side = court.side_of(own_x);
+    let toward_net = (court.net_x - own_x).signum();
+    let (box_min, box_max) = court.service_box(side);
+    let home_x = if toward_net > 0.0 { box_min } else { box_max };
+
+    let path = if ball_state.in_flight() {
+        predict_ball_path(
+            ball_transform.translation.truncate(),
+            ball_movement.velocity,
+            ball_spin.0,
+            ball_curve.0,
+            ball_size.0.y / 2.0,
+            court.ground_y,
+            &match_config.modifiers,
+        )
+    } else {
+        Vec::new()
+    };
+    let read_ahead = path.len().min(read_ahead_ticks + 1);
+    let interception = intercept(&path[..read_ahead], court, own_x, transform.translation.y);
+
+    // Stand so the racket, on the side facing the net, lines up with the ball
+    let target_x = interception.map_or(home_x, |(_, point)| point.x - toward_net * size.0.x / 2.0);
+    Positioning {
+        offset: target_x - own_x,
+        toward_net,
+        interception,
+    }
+}
+
+pub fn ai_controller_system(
+    court: Res<Court>,
+    match_config: Res<MatchConfig>,
+    match_state: Res<MatchState>,
+    serve_state: Res<ServeState>,
+    ball_query: BallQuery,
+    mut query: Query<(&AiController, &Transform, &Size, &mut PlayerInput), With<Player>>,
+) {
+    let (ball_transform, _, _, _, ball_size, ball_state) = ball_query.single();
+    for (controller, transform, size, mut input) in &mut query {
+        let Positioning {
+            offset,
+            toward_net,
+            interception,
+        } = plan_position(
+            &court,
+            &match_config,
+            &ball_query,
+            transform,
+            size,
+            controller.difficulty.read_ahead_ticks(),
+        );
+        let side = court.side_of(transform.translation.x);
+        input.left = offset < -POSITION_TOLERANCE;
+        input.right = offset > POSITION_TOLERANCE;
+        input.jump = false;
+        input.jump_pressed = false;
+        // Toss as soon as the AI is back in position to serve
+        let toss = *serve_state == ServeState::Ready
+            && *ball_state == BallState::Held
+            && side == match_state.server()
+            && offset.abs() <= POSITION_TOLERANCE;
+
+        let swing_soon = interception.is_some_and(|(tick, _)| tick <= SWING_LOOKAHEAD_TICKS);
+        let ball_in_reach = racket_center(transform, size)
+            .distance(ball_transform.translation.truncate())
+            < RACKET_SIZE + ball_size.0.x;
+        let swing = ball_state.in_flight() && (swing_soon || ball_in_reach);
+        // Pressed every tick it wants to swing, a new swing only starts once the last
+        // one has recovered
+        input.swing_pressed = toss || swing;
+        // Turn back to face the net before swinging
+        if swing && facing(transform) != toward_net {
+            input.left = toward_net < 0.0;
+            input.right = toward_net > 0.0;
+        }
+    }
+}
+
+type AutoMoveQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Transform, &'static Size, &'static mut PlayerInput),
+    (With<Player>, With<AutoMove>, Without<AiController>),
+>;
+
+// Runs after the human controllers and only takes over the running, the player's own
+// swing button still decides when to hit
+pub fn auto_move_system(
+    court: Res<Court>,
+    match_config: Res<MatchConfig>,
+    ball_query: BallQuery,
+    mut query: AutoMoveQuery,
+) {
+    for (transform, size, mut input) in &mut query {
+        let Positioning {
+            offset, toward_net, ..
+        } = plan_position(
+            &court,
+            &match_config,
+            &ball_query,
+            transform,
+            size,
+            MAX_PREDICTION_TICKS,
+        );
+        input.left = offset < -POSITION_TOLERANCE;
+        input.right = offset > POSITION_TOLERANCE;
+        // Held directions aim the return, so none are held through the swing
+        if input.swing_pressed {
+            input.left = facing(transform) != toward_net && toward_net < 0.0;
+            input.right = facing(transform) != toward_net && toward_net > 0.0;
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! A trail behind a curving ball, so the bend of a curve shot can be seen. Only a
+//! ball with enough curve left on it leaves one, other shots fly clean.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    ball_movement_system, collision_system, game_state::GameplaySet, Ball, BallState, Curve,
+};
+
+// Ticks of positions kept, the trail's length
+const TRAIL_LENGTH: usize = 30;
+// Curve below this is too faint to be worth showing
+const MIN_TRAIL_CURVE: f32 = 40.;
+const TRAIL_COLOR: Color = Color::CYAN;
+
+#[derive(Resource, Default)]
+struct BallTrail(VecDeque<Vec2>);
+
+fn record_trail_system(
+    mut trail: ResMut<BallTrail>,
+    ball_query: Query<(&Transform, &BallState, &Curve), With<Ball>>,
+) {
+    let (transform, state, curve) = ball_query.single();
+    if !state.in_flight() || curve.0.length() < MIN_TRAIL_CURVE {
+        trail.0.clear();
+        return;
+    }
+    trail.0.push_back(transform.translation.truncate());
+    if trail.0.len() > TRAIL_LENGTH {
+        trail.0.pop_front();
+    }
+}
+
+fn draw_trail_system(trail: Res<BallTrail>, mut gizmos: Gizmos) {
+    gizmos.linestrip_2d(trail.0.iter().copied(), TRAIL_COLOR);
+}
+
+pub struct BallTrailPlugin;
+
+impl Plugin for BallTrailPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BallTrail>()
+            .add_systems(
+                FixedUpdate,
+                record_trail_system
+                    .after(ball_movement_system)
+                    .after(collision_system::<Ball>)
+                    .in_set(GameplaySet),
+            )
+            .add_systems(Update, draw_trail_system);
+    }
+}
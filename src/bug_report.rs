@@ -0,0 +1,78 @@
+//! F12 writes a plain text bug report with the tuning values and the physics state of
+//! every moving entity, so odd physics can be reported with the numbers that caused it.
+
+use std::{
+    fmt::Write as _,
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+
+use crate::{Ball, Movement, Player, TUNING};
+
+const BUG_REPORT_DIR: &str = "bug_reports";
+
+pub fn write_tuning(report: &mut String) {
+    report.push_str("[tuning]\n");
+    for (name, value) in TUNING {
+        let _ = writeln!(report, "{name} = {value}");
+    }
+}
+
+type ReportQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        &'static Movement,
+        Option<&'static Player>,
+        Option<&'static Ball>,
+    ),
+>;
+
+fn bug_report_system(keyboard_input: Res<Input<KeyCode>>, query: ReportQuery) {
+    if !keyboard_input.just_pressed(KeyCode::F12) {
+        return;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let mut report = String::new();
+    write_tuning(&mut report);
+    for (entity, transform, movement, player, ball) in &query {
+        let kind = if player.is_some() {
+            "player"
+        } else if ball.is_some() {
+            "ball"
+        } else {
+            "entity"
+        };
+        let _ = writeln!(
+            report,
+            "\n[{kind} {entity:?}]\ntranslation = {}\nvelocity = {}\nvelocity_remainder = {}\non_ground = {}",
+            transform.translation,
+            movement.velocity,
+            movement.velocity_remainder,
+            movement.on_ground,
+        );
+    }
+
+    let path = PathBuf::from(BUG_REPORT_DIR).join(format!("report-{timestamp}.txt"));
+    match fs::create_dir_all(BUG_REPORT_DIR).and_then(|_| fs::write(&path, report)) {
+        Ok(()) => info!("Wrote bug report to {}", path.display()),
+        Err(error) => error!("Failed to write bug report: {error}"),
+    }
+}
+
+pub struct BugReportPlugin;
+
+impl Plugin for BugReportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, bug_report_system);
+    }
+}
@@ -0,0 +1,105 @@
+//! Limits how many FixedUpdate ticks a single frame can simulate. After a hitch the
+//! fixed timestep would otherwise run every tick it fell behind on back to back, and
+//! everything on screen jumps. Ticks over the limit still drain the accumulator but
+//! skip the gameplay systems, and are counted in the `dropped_ticks` diagnostic.
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics, RegisterDiagnostic},
+    prelude::*,
+};
+
+use crate::console::ConsoleAppExt;
+
+pub const DROPPED_TICKS: DiagnosticId =
+    DiagnosticId::from_u128(0x7c4e_21b9_58d3_4f0a_9e61_3a2d_c85b_04f7);
+
+// Five ticks is a 12 fps frame, anything slower is a hitch rather than a slow machine
+const DEFAULT_MAX_TICKS_PER_FRAME: u32 = 5;
+
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CatchUpSet;
+
+#[derive(Resource)]
+pub struct CatchUp {
+    pub max_ticks_per_frame: u32,
+    ticks_this_frame: u32,
+    dropped_this_frame: u32,
+    dropped_total: u64,
+}
+
+impl Default for CatchUp {
+    fn default() -> Self {
+        Self {
+            max_ticks_per_frame: DEFAULT_MAX_TICKS_PER_FRAME,
+            ticks_this_frame: 0,
+            dropped_this_frame: 0,
+            dropped_total: 0,
+        }
+    }
+}
+
+impl CatchUp {
+    pub fn dropped_total(&self) -> u64 {
+        self.dropped_total
+    }
+}
+
+fn count_fixed_tick_system(mut catch_up: ResMut<CatchUp>) {
+    catch_up.ticks_this_frame += 1;
+    if catch_up.ticks_this_frame > catch_up.max_ticks_per_frame {
+        catch_up.dropped_this_frame += 1;
+        catch_up.dropped_total += 1;
+    }
+}
+
+pub fn within_catch_up_limit(catch_up: Res<CatchUp>) -> bool {
+    catch_up.ticks_this_frame <= catch_up.max_ticks_per_frame
+}
+
+// Runs after the fixed update loop, so the measurement covers the whole frame
+fn end_catch_up_frame_system(mut catch_up: ResMut<CatchUp>, mut diagnostics: Diagnostics) {
+    let dropped = catch_up.dropped_this_frame;
+    if dropped > 0 {
+        warn!("Dropped {dropped} fixed update ticks to catch up after a slow frame");
+    }
+    diagnostics.add_measurement(DROPPED_TICKS, || dropped as f64);
+    catch_up.ticks_this_frame = 0;
+    catch_up.dropped_this_frame = 0;
+}
+
+fn catch_up_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let mut catch_up = world.resource_mut::<CatchUp>();
+    match args {
+        [] => Ok(format!(
+            "max {} ticks per frame, {} dropped",
+            catch_up.max_ticks_per_frame,
+            catch_up.dropped_total()
+        )),
+        [ticks] => {
+            let ticks: u32 = ticks
+                .parse()
+                .ok()
+                .filter(|ticks| *ticks > 0)
+                .ok_or_else(|| format!("'{ticks}' is not a positive whole number"))?;
+            catch_up.max_ticks_per_frame = ticks;
+            Ok(format!("max {ticks} ticks per frame"))
+        }
+        _ => Err("usage: catchup [max ticks per frame]".to_string()),
+    }
+}
+
+pub struct CatchUpPlugin;
+
+impl Plugin for CatchUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CatchUp>()
+            .register_diagnostic(Diagnostic::new(DROPPED_TICKS, "dropped_ticks", 20))
+            .add_console_command(
+                "catchup",
+                "catchup [ticks], show or set the fixed update ticks allowed per frame",
+                catch_up_command,
+            )
+            .add_systems(FixedUpdate, count_fixed_tick_system.in_set(CatchUpSet))
+            .add_systems(Last, end_catch_up_frame_system);
+    }
+}
@@ -0,0 +1,340 @@
+//! Playable characters, defined as data in `assets/characters.txt` and picked with the
+//! `character` console command. The right player always takes the palette after the
+//! left one, so a mirror match still tells the two apart.
+
+use bevy::{asset::FileAssetIo, prelude::*};
+
+use crate::{console::ConsoleAppExt, court::CourtSide, AnimationIndices, Player};
+
+const CHARACTERS_FILE: &str = "characters.txt";
+
+#[derive(Component, Clone, Copy)]
+pub struct AnimationLibrary {
+    pub idle: AnimationIndices,
+    pub run: AnimationIndices,
+    pub jump: AnimationIndices,
+    pub dash: AnimationIndices,
+    pub dive: AnimationIndices,
+    pub knockdown: AnimationIndices,
+}
+
+pub struct CharacterDef {
+    pub name: String,
+    pub atlas: String,
+    pub tile_size: Vec2,
+    pub columns: usize,
+    pub rows: usize,
+    pub animations: AnimationLibrary,
+    /// Sprite tints, one per palette variant. Never empty.
+    pub palettes: Vec<Color>,
+}
+
+impl CharacterDef {
+    // The stock player sprite, used when the characters file gives us nothing to play
+    fn builtin() -> Self {
+        Self {
+            name: "default".to_string(),
+            atlas: "player_atlas.png".to_string(),
+            tile_size: Vec2::new(8.0, 8.0),
+            columns: 16,
+            rows: 3,
+            animations: AnimationLibrary {
+                idle: AnimationIndices {
+                    first: 15,
+                    last: 15,
+                },
+                run: AnimationIndices {
+                    first: 18,
+                    last: 21,
+                },
+                jump: AnimationIndices {
+                    first: 17,
+                    last: 17,
+                },
+                dash: AnimationIndices {
+                    first: 19,
+                    last: 19,
+                },
+                dive: AnimationIndices {
+                    first: 17,
+                    last: 17,
+                },
+                knockdown: AnimationIndices {
+                    first: 16,
+                    last: 16,
+                },
+            },
+            palettes: vec![Color::WHITE, Color::rgb(1.0, 0.6, 0.6)],
+        }
+    }
+
+    pub fn palette(&self, index: usize) -> Color {
+        self.palettes[index % self.palettes.len()]
+    }
+}
+
+// A character as far as the file has filled it in
+#[derive(Default)]
+struct PartialCharacter {
+    name: String,
+    atlas: Option<String>,
+    tile_size: Option<Vec2>,
+    grid: Option<(usize, usize)>,
+    idle: Option<AnimationIndices>,
+    run: Option<AnimationIndices>,
+    jump: Option<AnimationIndices>,
+    dash: Option<AnimationIndices>,
+    dive: Option<AnimationIndices>,
+    knockdown: Option<AnimationIndices>,
+    palettes: Vec<Color>,
+}
+
+impl PartialCharacter {
+    fn set(&mut self, key: &str, values: &[&str]) -> Option<()> {
+        let numbers = || {
+            values
+                .iter()
+                .map(|value| value.parse::<f32>().ok())
+                .collect::<Option<Vec<f32>>>()
+        };
+        let frames = || match values {
+            [first, last] => Some(AnimationIndices {
+                first: first.parse().ok()?,
+                last: last.parse().ok()?,
+            }),
+            _ => None,
+        };
+        match key {
+            "atlas" => self.atlas = Some(values.first()?.to_string()),
+            "tile" => {
+                let &[width, height] = numbers()?.as_slice() else {
+                    return None;
+                };
+                self.tile_size = Some(Vec2::new(width, height));
+            }
+            "grid" => {
+                let [columns, rows] = values else {
+                    return None;
+                };
+                self.grid = Some((columns.parse().ok()?, rows.parse().ok()?));
+            }
+            "idle" => self.idle = Some(frames()?),
+            "run" => self.run = Some(frames()?),
+            "jump" => self.jump = Some(frames()?),
+            "dash" => self.dash = Some(frames()?),
+            "dive" => self.dive = Some(frames()?),
+            "knockdown" => self.knockdown = Some(frames()?),
+            "palette" => {
+                let &[red, green, blue] = numbers()?.as_slice() else {
+                    return None;
+                };
+                self.palettes.push(Color::rgb(red, green, blue));
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    fn finish(self) -> Option<CharacterDef> {
+        let (columns, rows) = self.grid?;
+        Some(CharacterDef {
+            name: self.name,
+            atlas: self.atlas?,
+            tile_size: self.tile_size?,
+            columns,
+            rows,
+            animations: AnimationLibrary {
+                idle: self.idle?,
+                run: self.run?,
+                jump: self.jump?,
+                dash: self.dash?,
+                dive: self.dive?,
+                knockdown: self.knockdown?,
+            },
+            palettes: if self.palettes.is_empty() {
+                vec![Color::WHITE]
+            } else {
+                self.palettes
+            },
+        })
+    }
+}
+
+/// Every character in `assets/characters.txt`, in file order.
+#[derive(Resource)]
+pub struct Characters(Vec<CharacterDef>);
+
+impl Characters {
+    /// Lines that don't parse and characters missing a field are skipped with a
+    /// warning. If the file is missing or defines no characters, the built-in default
+    /// character is used instead.
+    fn load() -> Self {
+        let path = FileAssetIo::get_base_path()
+            .join("assets")
+            .join(CHARACTERS_FILE);
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) => {
+                warn!(
+                    "Couldn't read {}: {error}, using the default character",
+                    path.display()
+                );
+                String::new()
+            }
+        };
+
+        let mut characters = Vec::new();
+        let mut current: Option<PartialCharacter> = None;
+        let mut finish = |partial: PartialCharacter| {
+            let name = partial.name.clone();
+            match partial.finish() {
+                Some(def) => characters.push(def),
+                None => warn!("Character '{name}' is missing fields and was skipped"),
+            }
+        };
+        for line in contents.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let [key, values @ ..] = words.as_slice() else {
+                continue;
+            };
+            if key.starts_with('#') {
+                continue;
+            }
+            if *key == "character" {
+                if let Some(partial) = current.take() {
+                    finish(partial);
+                }
+                current = Some(PartialCharacter {
+                    name: values.join(" "),
+                    ..default()
+                });
+                continue;
+            }
+            let parsed = current
+                .as_mut()
+                .and_then(|partial| partial.set(key, values));
+            if parsed.is_none() {
+                warn!("Skipping character line '{line}'");
+            }
+        }
+        if let Some(partial) = current {
+            finish(partial);
+        }
+
+        if characters.is_empty() {
+            warn!(
+                "{} doesn't define any characters, using the default character",
+                path.display()
+            );
+            characters.push(CharacterDef::builtin());
+        }
+        Self(characters)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SelectedCharacter {
+    pub character: usize,
+    pub palette: usize,
+}
+
+impl SelectedCharacter {
+    pub fn def<'a>(&self, characters: &'a Characters) -> &'a CharacterDef {
+        &characters.0[self.character % characters.0.len()]
+    }
+
+    /// The right side takes the next palette along, so the opponent never looks like
+    /// the player.
+    pub fn tint(&self, characters: &Characters, side: CourtSide) -> Color {
+        self.def(characters).palette(self.palette + side.index())
+    }
+}
+
+pub fn load_character_atlas(
+    def: &CharacterDef,
+    asset_server: &AssetServer,
+    texture_atlases: &mut Assets<TextureAtlas>,
+) -> Handle<TextureAtlas> {
+    let texture_handle = asset_server.load(def.atlas.as_str());
+    let texture_atlas = TextureAtlas::from_grid(
+        texture_handle,
+        def.tile_size,
+        def.columns,
+        def.rows,
+        None,
+        None,
+    );
+    texture_atlases.add(texture_atlas)
+}
+
+// Players spawn with the selected character, so only later changes need a swap.
+pub fn swap_character_system(
+    selected: Res<SelectedCharacter>,
+    characters: Res<Characters>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut query: Query<
+        (
+            &mut Handle<TextureAtlas>,
+            &mut TextureAtlasSprite,
+            &mut AnimationLibrary,
+            &CourtSide,
+        ),
+        With<Player>,
+    >,
+) {
+    if !selected.is_changed() || selected.is_added() {
+        return;
+    }
+
+    let def = selected.def(&characters);
+    let atlas_handle = load_character_atlas(def, &asset_server, &mut texture_atlases);
+    for (mut atlas, mut sprite, mut library, side) in &mut query {
+        *atlas = atlas_handle.clone();
+        sprite.color = selected.tint(&characters, *side);
+        *library = def.animations;
+    }
+}
+
+fn character_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let characters = world.resource::<Characters>();
+    // Names can have spaces in them, so a trailing number is taken as the palette
+    let (name, palette) = match args {
+        [name @ .., palette] if !name.is_empty() && palette.parse::<usize>().is_ok() => {
+            (name.join(" "), Some(*palette))
+        }
+        [] => {
+            let names: Vec<&str> = characters.0.iter().map(|def| def.name.as_str()).collect();
+            return Err(format!(
+                "usage: character <name> [palette]\ncharacters: {}",
+                names.join(" ")
+            ));
+        }
+        name => (name.join(" "), None),
+    };
+    let Some(character) = characters.0.iter().position(|def| def.name == name) else {
+        return Err(format!("unknown character '{name}'"));
+    };
+    let palette = match palette {
+        Some(palette) => match palette.parse::<usize>() {
+            Ok(number) if (1..=characters.0[character].palettes.len()).contains(&number) => {
+                number - 1
+            }
+            _ => return Err(format!("'{palette}' is not a palette of {name}")),
+        },
+        None => 0,
+    };
+    *world.resource_mut::<SelectedCharacter>() = SelectedCharacter { character, palette };
+    Ok(format!("playing as {name}, palette {}", palette + 1))
+}
+
+pub struct CharacterPlugin;
+
+impl Plugin for CharacterPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Characters::load())
+            .init_resource::<SelectedCharacter>()
+            .add_console_command("character", "character <name> [palette]", character_command)
+            .add_systems(Update, swap_character_system);
+    }
+}
@@ -0,0 +1,110 @@
+//! Practice console commands for setting up exact scenarios. They are turned off in
+//! the daily challenge, so its best results stay fair.
+
+use bevy::prelude::*;
+
+use crate::{
+    console::ConsoleAppExt, match_config::MatchConfig, stamina::InfiniteStamina, Ball, BallState,
+    Movement, PlayerId, Spin,
+};
+
+// What the ball was doing when it was frozen, so releasing it carries on from there
+#[derive(Resource, Default)]
+struct FrozenBall(Option<(BallState, Vec2)>);
+
+fn cheats_allowed(world: &World) -> Result<(), String> {
+    if world.resource::<MatchConfig>().daily.is_some() {
+        return Err("cheats are off in the daily challenge".to_string());
+    }
+    Ok(())
+}
+
+fn freeze_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    cheats_allowed(world)?;
+    let frozen = world.resource_mut::<FrozenBall>().0.take();
+    let mut query = world.query_filtered::<(&mut Movement, &mut BallState), With<Ball>>();
+    let (mut movement, mut state) = query
+        .get_single_mut(world)
+        .map_err(|_| "there is no ball".to_string())?;
+    if let Some((frozen_state, velocity)) = frozen {
+        // Something else may have put the ball back in play since
+        if *state == BallState::Held {
+            *state = frozen_state;
+            movement.velocity = velocity;
+        }
+        return Ok("ball released".to_string());
+    }
+    let saved = (*state, movement.velocity);
+    movement.velocity = Vec2::ZERO;
+    movement.velocity_remainder = Vec2::ZERO;
+    *state = BallState::Held;
+    world.resource_mut::<FrozenBall>().0 = Some(saved);
+    Ok("ball frozen".to_string())
+}
+
+fn spin_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    cheats_allowed(world)?;
+    let [value] = args else {
+        return Err("usage: spin <radians per second>".to_string());
+    };
+    let value: f32 = value
+        .parse()
+        .map_err(|_| format!("'{value}' is not a number"))?;
+    let mut query = world.query_filtered::<&mut Spin, With<Ball>>();
+    let mut spin = query
+        .get_single_mut(world)
+        .map_err(|_| "there is no ball".to_string())?;
+    spin.0 = value;
+    Ok(format!("ball spin set to {value}"))
+}
+
+fn stamina_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    cheats_allowed(world)?;
+    let mut infinite = world.resource_mut::<InfiniteStamina>();
+    infinite.0 = !infinite.0;
+    Ok(format!(
+        "infinite stamina {}",
+        if infinite.0 { "on" } else { "off" }
+    ))
+}
+
+fn teleport_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    cheats_allowed(world)?;
+    let (x, y, player) = match args {
+        [x, y] => (x, y, "1"),
+        [x, y, player] => (x, y, *player),
+        _ => return Err("usage: teleport <x> <y> [player]".to_string()),
+    };
+    let (x, y): (f32, f32) = match (x.parse(), y.parse()) {
+        (Ok(x), Ok(y)) => (x, y),
+        _ => return Err("coordinates must be numbers".to_string()),
+    };
+    // Players are numbered from 1 in the console
+    let id = match player.parse::<usize>() {
+        Ok(number) if number > 0 => PlayerId(number - 1),
+        _ => return Err(format!("'{player}' is not a player number")),
+    };
+    let mut query = world.query::<(&PlayerId, &mut Transform, &mut Movement)>();
+    let (_, mut transform, mut movement) = query
+        .iter_mut(world)
+        .find(|(player_id, ..)| **player_id == id)
+        .ok_or_else(|| format!("there is no player {player}"))?;
+    transform.translation.x = x;
+    transform.translation.y = y;
+    movement.velocity = Vec2::ZERO;
+    movement.velocity_remainder = Vec2::ZERO;
+    movement.on_ground = false;
+    Ok(format!("player {player} teleported to ({x}, {y})"))
+}
+
+pub struct CheatsPlugin;
+
+impl Plugin for CheatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FrozenBall>()
+            .add_console_command("freeze", "freeze or release the ball", freeze_command)
+            .add_console_command("teleport", "teleport <x> <y> [player]", teleport_command)
+            .add_console_command("spin", "spin <radians per second>", spin_command)
+            .add_console_command("stamina", "toggle infinite stamina", stamina_command);
+    }
+}
@@ -0,0 +1,291 @@
+//! Developer console, opened with the backtick key. Commands live in a registry that
+//! plugins extend with `add_console_command`, and handlers get full world access.
+
+use std::collections::{HashMap, VecDeque};
+
+use bevy::{prelude::*, window::ReceivedCharacter};
+
+use crate::{serve::ServeState, Ball, BallState, Bounces, Movement};
+
+const MAX_LOG_LINES: usize = 12;
+
+pub type ConsoleHandler = fn(&mut World, &[&str]) -> Result<String, String>;
+
+struct ConsoleCommand {
+    help: &'static str,
+    handler: ConsoleHandler,
+}
+
+#[derive(Resource, Default)]
+struct ConsoleCommands(HashMap<&'static str, ConsoleCommand>);
+
+#[derive(Resource, Default)]
+pub struct Console {
+    open: bool,
+    input: String,
+    pending: Vec<String>,
+    log: VecDeque<String>,
+}
+
+impl Console {
+    fn print(&mut self, line: String) {
+        self.log.push_back(line);
+        if self.log.len() > MAX_LOG_LINES {
+            self.log.pop_front();
+        }
+    }
+}
+
+/// Systems that can be switched on and off at runtime with `toggle <name> on|off`.
+#[derive(Resource, Default)]
+pub struct SystemToggles(HashMap<&'static str, bool>);
+
+/// Run condition for gameplay input, so typing a command doesn't also play the game.
+pub fn console_closed(console: Res<Console>) -> bool {
+    !console.open
+}
+
+pub fn system_enabled(name: &'static str) -> impl FnMut(Res<SystemToggles>) -> bool + Clone {
+    move |toggles: Res<SystemToggles>| toggles.0.get(name).copied().unwrap_or(true)
+}
+
+pub trait ConsoleAppExt {
+    fn add_console_command(
+        &mut self,
+        name: &'static str,
+        help: &'static str,
+        handler: ConsoleHandler,
+    ) -> &mut Self;
+
+    fn add_system_toggle(&mut self, name: &'static str, enabled: bool) -> &mut Self;
+}
+
+impl ConsoleAppExt for App {
+    fn add_console_command(
+        &mut self,
+        name: &'static str,
+        help: &'static str,
+        handler: ConsoleHandler,
+    ) -> &mut Self {
+        self.init_resource::<ConsoleCommands>();
+        self.world
+            .resource_mut::<ConsoleCommands>()
+            .0
+            .insert(name, ConsoleCommand { help, handler });
+        self
+    }
+
+    fn add_system_toggle(&mut self, name: &'static str, enabled: bool) -> &mut Self {
+        self.init_resource::<SystemToggles>();
+        self.world
+            .resource_mut::<SystemToggles>()
+            .0
+            .insert(name, enabled);
+        self
+    }
+}
+
+fn parse_args<const N: usize>(args: &[&str]) -> Result<[f32; N], String> {
+    if args.len() != N {
+        return Err(format!("expected {N} arguments, got {}", args.len()));
+    }
+    let mut values = [0.0; N];
+    for (value, arg) in values.iter_mut().zip(args) {
+        *value = arg
+            .parse()
+            .map_err(|_| format!("'{arg}' is not a number"))?;
+    }
+    Ok(values)
+}
+
+fn help_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    let commands = world.resource::<ConsoleCommands>();
+    let mut lines: Vec<String> = commands
+        .0
+        .iter()
+        .map(|(name, command)| format!("{name}: {}", command.help))
+        .collect();
+    lines.sort();
+    Ok(lines.join("\n"))
+}
+
+fn toggle_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [name, state] = args else {
+        return Err("usage: toggle <name> on|off".to_string());
+    };
+    let enabled = match *state {
+        "on" => true,
+        "off" => false,
+        _ => return Err(format!("'{state}' is neither on nor off")),
+    };
+    let mut toggles = world.resource_mut::<SystemToggles>();
+    let Some(toggle) = toggles.0.get_mut(*name) else {
+        return Err(format!("unknown system '{name}'"));
+    };
+    *toggle = enabled;
+    Ok(format!("{name} {state}"))
+}
+
+// The game assumes a single ball, so this relaunches it as a serve rather than
+// spawning another
+fn ball_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [x, y, velocity_x, velocity_y] = parse_args(args)?;
+    let mut query = world.query_filtered::<(
+        &mut Transform,
+        &mut Movement,
+        &mut Bounces,
+        &mut BallState,
+    ), With<Ball>>();
+    let (mut transform, mut movement, mut bounces, mut state) = query
+        .get_single_mut(world)
+        .map_err(|_| "there is no ball".to_string())?;
+    transform.translation.x = x;
+    transform.translation.y = y;
+    // Movement treats positive y as down, the console treats it as up
+    movement.velocity = Vec2::new(velocity_x, -velocity_y);
+    movement.velocity_remainder = Vec2::ZERO;
+    *bounces = Bounces::default();
+    *state = BallState::Served;
+    *world.resource_mut::<ServeState>() = ServeState::InPlay;
+    Ok(format!("ball launched from ({x}, {y})"))
+}
+
+#[derive(Component)]
+struct ConsoleRoot;
+
+#[derive(Component)]
+struct ConsoleText;
+
+fn setup_console_system(mut commands: Commands) {
+    commands
+        .spawn((
+            ConsoleRoot,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(0.0),
+                    left: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.75).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ConsoleText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+fn console_input_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut console: ResMut<Console>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Grave) {
+        console.open = !console.open;
+        characters.clear();
+        return;
+    }
+    if !console.open {
+        characters.clear();
+        return;
+    }
+
+    for character in characters.iter() {
+        if !character.char.is_control() {
+            console.input.push(character.char);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        console.print(format!("> {line}"));
+        console.pending.push(line);
+    }
+}
+
+fn run_console_commands_system(world: &mut World) {
+    let pending = std::mem::take(&mut world.resource_mut::<Console>().pending);
+    for line in pending {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let Some((name, args)) = args.split_first() else {
+            continue;
+        };
+        let handler = world
+            .resource::<ConsoleCommands>()
+            .0
+            .get(*name)
+            .map(|command| command.handler);
+        let result = match handler {
+            Some(handler) => handler(world, args),
+            None => Err(format!("unknown command '{name}', try help")),
+        };
+        let output = result.unwrap_or_else(|error| format!("error: {error}"));
+        let mut console = world.resource_mut::<Console>();
+        for output_line in output.lines() {
+            console.print(output_line.to_string());
+        }
+    }
+}
+
+fn update_console_ui_system(
+    console: Res<Console>,
+    mut root_query: Query<&mut Visibility, With<ConsoleRoot>>,
+    mut text_query: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+
+    *root_query.single_mut() = if console.open {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    let mut text = text_query.single_mut();
+    let mut value: Vec<&str> = console.log.iter().map(String::as_str).collect();
+    let prompt = format!("> {}_", console.input);
+    value.push(&prompt);
+    text.sections[0].value = value.join("\n");
+}
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .init_resource::<SystemToggles>()
+            .add_console_command("help", "list commands", help_command)
+            .add_console_command("toggle", "toggle <name> on|off", toggle_command)
+            .add_console_command(
+                "ball",
+                "ball <x> <y> <vx> <vy>, positive vy is up",
+                ball_command,
+            )
+            .add_systems(Startup, setup_console_system)
+            .add_systems(
+                Update,
+                (
+                    console_input_system,
+                    run_console_commands_system,
+                    update_console_ui_system,
+                )
+                    .chain(),
+            );
+    }
+}
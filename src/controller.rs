@@ -0,0 +1,291 @@
+//! Where a player's input comes from. Controller systems fill in `PlayerInput` each
+//! tick and `player_movement_system` only ever reads that, so a player can be driven
+//! by the keyboard or by the AI without the movement code knowing which.
+//!
+//! Keys are looked up per action in the `InputMap`, which `bind` changes at runtime and
+//! saves to the profile directory.
+
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use bevy::{prelude::*, reflect::DynamicEnum};
+
+use crate::{console::ConsoleAppExt, match_config::MatchConfig, PROFILE_DIR};
+
+const INPUT_MAP_FILE: &str = "input_map.txt";
+
+/// The buttons a player is holding this tick.
+#[derive(Component, Default)]
+pub struct PlayerInput {
+    pub left: bool,
+    pub right: bool,
+    pub jump: bool,
+    // Only true on the tick the jump button goes down
+    pub jump_pressed: bool,
+    // Starts a swing, which then plays out on its own
+    pub swing_pressed: bool,
+    pub dash_pressed: bool,
+    pub dive_pressed: bool,
+    // Held through a swing to pick the shot instead of a drive
+    pub lob: bool,
+    pub drop: bool,
+    // Held through a swing to bend the shot
+    pub curve: bool,
+}
+
+/// Reads a player's input from the keyboard, using the key layout at this index in the
+/// `InputMap`. Two players can share a keyboard with different layouts.
+#[derive(Component, Default)]
+pub struct KeyboardController(pub usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Swing,
+    Lob,
+    Drop,
+    Dash,
+    Dive,
+    Curve,
+}
+
+impl Action {
+    const ALL: [Action; 9] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Jump,
+        Action::Swing,
+        Action::Lob,
+        Action::Drop,
+        Action::Dash,
+        Action::Dive,
+        Action::Curve,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "left",
+            Action::MoveRight => "right",
+            Action::Jump => "jump",
+            Action::Swing => "swing",
+            Action::Lob => "lob",
+            Action::Drop => "drop",
+            Action::Dash => "dash",
+            Action::Dive => "dive",
+            Action::Curve => "curve",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+// Key names are the KeyCode variant names, e.g. Up or ShiftLeft
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    KeyCode::from_reflect(&DynamicEnum::new(name, ()))
+}
+
+#[derive(Clone)]
+pub struct InputBindings(HashMap<Action, KeyCode>);
+
+impl InputBindings {
+    pub fn arrows() -> Self {
+        Self(HashMap::from([
+            (Action::MoveLeft, KeyCode::Left),
+            (Action::MoveRight, KeyCode::Right),
+            (Action::Jump, KeyCode::Up),
+            (Action::Swing, KeyCode::Space),
+            (Action::Lob, KeyCode::ShiftRight),
+            (Action::Drop, KeyCode::Down),
+            (Action::Dash, KeyCode::ControlRight),
+            (Action::Dive, KeyCode::AltRight),
+            (Action::Curve, KeyCode::Slash),
+        ]))
+    }
+
+    // The second player on a shared keyboard
+    pub fn wasd() -> Self {
+        Self(HashMap::from([
+            (Action::MoveLeft, KeyCode::A),
+            (Action::MoveRight, KeyCode::D),
+            (Action::Jump, KeyCode::W),
+            (Action::Swing, KeyCode::ShiftLeft),
+            (Action::Lob, KeyCode::Q),
+            (Action::Drop, KeyCode::S),
+            (Action::Dash, KeyCode::E),
+            (Action::Dive, KeyCode::R),
+            (Action::Curve, KeyCode::F),
+        ]))
+    }
+
+    // A third player squeezed in between the other two, for king of the court
+    pub fn ijkl() -> Self {
+        Self(HashMap::from([
+            (Action::MoveLeft, KeyCode::J),
+            (Action::MoveRight, KeyCode::L),
+            (Action::Jump, KeyCode::I),
+            (Action::Swing, KeyCode::O),
+            (Action::Lob, KeyCode::U),
+            (Action::Drop, KeyCode::K),
+            (Action::Dash, KeyCode::P),
+            (Action::Dive, KeyCode::Semicolon),
+            (Action::Curve, KeyCode::H),
+        ]))
+    }
+
+    pub fn key(&self, action: Action) -> Option<KeyCode> {
+        self.0.get(&action).copied()
+    }
+}
+
+/// Key layouts, the arrow keys, WASD and IJKL by default.
+#[derive(Resource)]
+pub struct InputMap(Vec<InputBindings>);
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self(vec![
+            InputBindings::arrows(),
+            InputBindings::wasd(),
+            InputBindings::ijkl(),
+        ])
+    }
+}
+
+impl InputMap {
+    fn path() -> PathBuf {
+        PathBuf::from(PROFILE_DIR).join(INPUT_MAP_FILE)
+    }
+
+    pub fn layout_count(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn bindings(&self, layout: usize) -> Option<&InputBindings> {
+        self.0.get(layout)
+    }
+
+    pub fn bind(&mut self, layout: usize, action: Action, key: KeyCode) {
+        if self.0.len() <= layout {
+            self.0.resize(layout + 1, InputBindings(HashMap::new()));
+        }
+        self.0[layout].0.insert(action, key);
+    }
+
+    /// The defaults with any saved bindings applied on top. Lines that don't parse are
+    /// skipped, so a hand-edited file can't lock anyone out of the controls.
+    fn load() -> Self {
+        let mut input_map = Self::default();
+        let Ok(contents) = fs::read_to_string(Self::path()) else {
+            return input_map;
+        };
+        for line in contents.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let [layout, action, key] = words.as_slice() else {
+                continue;
+            };
+            let layout = layout.parse::<usize>().ok().filter(|number| *number > 0);
+            if let (Some(layout), Some(action), Some(key)) =
+                (layout, Action::from_name(action), key_from_name(key))
+            {
+                input_map.bind(layout - 1, action, key);
+            }
+        }
+        input_map
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let mut lines = Vec::new();
+        for (index, bindings) in self.0.iter().enumerate() {
+            for action in Action::ALL {
+                if let Some(key) = bindings.key(action) {
+                    lines.push(format!("{} {} {key:?}", index + 1, action.name()));
+                }
+            }
+        }
+        fs::create_dir_all(PROFILE_DIR)?;
+        fs::write(Self::path(), lines.join("\n"))
+    }
+}
+
+/// Releases every button before the controllers fill them in again, so nothing stays
+/// held once its device stops reporting it or its controller is switched off.
+pub fn reset_player_input_system(mut query: Query<&mut PlayerInput>) {
+    for mut input in &mut query {
+        *input = PlayerInput::default();
+    }
+}
+
+pub fn keyboard_controller_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    match_config: Res<MatchConfig>,
+    mut query: Query<(&KeyboardController, &mut PlayerInput)>,
+) {
+    for (controller, mut input) in &mut query {
+        let Some(bindings) = input_map.bindings(controller.0) else {
+            continue;
+        };
+        let pressed = |action| {
+            bindings
+                .key(action)
+                .is_some_and(|key| keyboard_input.pressed(key))
+        };
+        input.left = pressed(Action::MoveLeft);
+        input.right = pressed(Action::MoveRight);
+        input.jump = pressed(Action::Jump);
+        input.jump_pressed = bindings
+            .key(Action::Jump)
+            .is_some_and(|key| keyboard_input.just_pressed(key));
+        input.swing_pressed = bindings
+            .key(Action::Swing)
+            .is_some_and(|key| keyboard_input.just_pressed(key));
+        input.dash_pressed = bindings
+            .key(Action::Dash)
+            .is_some_and(|key| keyboard_input.just_pressed(key));
+        input.dive_pressed = bindings
+            .key(Action::Dive)
+            .is_some_and(|key| keyboard_input.just_pressed(key));
+        input.lob = pressed(Action::Lob);
+        input.drop = pressed(Action::Drop);
+        input.curve = pressed(Action::Curve);
+        if match_config.modifiers.mirrored_controls {
+            (input.left, input.right) = (input.right, input.left);
+        }
+    }
+}
+
+fn bind_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [layout, action, key] = args else {
+        return Err(
+            "usage: bind <keyboard> left|right|jump|swing|lob|drop|dash|dive|curve <key>"
+                .to_string(),
+        );
+    };
+    let index = match layout.parse::<usize>() {
+        Ok(number) if number > 0 => number - 1,
+        _ => return Err(format!("'{layout}' is not a keyboard layout number")),
+    };
+    let action = Action::from_name(action).ok_or_else(|| format!("unknown action '{action}'"))?;
+    let key = key_from_name(key).ok_or_else(|| format!("unknown key '{key}'"))?;
+    let mut input_map = world.resource_mut::<InputMap>();
+    input_map.bind(index, action, key);
+    input_map
+        .save()
+        .map_err(|error| format!("bound but not saved: {error}"))?;
+    Ok(format!("keyboard {layout} {} is {key:?}", action.name()))
+}
+
+pub struct ControllerPlugin;
+
+impl Plugin for ControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputMap::load()).add_console_command(
+            "bind",
+            "bind <keyboard> left|right|jump|swing|lob|drop|dash|dive|curve <key>",
+            bind_command,
+        );
+    }
+}
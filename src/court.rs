@@ -0,0 +1,68 @@
+use bevy::prelude::*;
+
+pub const NET_HEIGHT: f32 = 48.;
+pub const NET_THICKNESS: f32 = 4.;
+pub const SERVICE_LINE_DISTANCE: f32 = 256.;
+pub const BASELINE_DISTANCE: f32 = 480.;
+
+/// The half of the court a player is on, also kept on each player entity.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CourtSide {
+    Left,
+    Right,
+}
+
+impl CourtSide {
+    pub fn opposite(self) -> Self {
+        match self {
+            CourtSide::Left => CourtSide::Right,
+            CourtSide::Right => CourtSide::Left,
+        }
+    }
+
+    pub fn index(self) -> usize {
+        match self {
+            CourtSide::Left => 0,
+            CourtSide::Right => 1,
+        }
+    }
+}
+
+// Distances are measured along x from the net, heights from the ground surface.
+#[derive(Resource)]
+pub struct Court {
+    pub net_x: f32,
+    pub ground_y: f32,
+    pub net_height: f32,
+    // 0 for a slack net up to 1 for a tight one
+    pub net_tension: f32,
+    pub service_line: f32,
+    pub baseline: f32,
+}
+
+impl Court {
+    pub fn side_of(&self, x: f32) -> CourtSide {
+        if x < self.net_x {
+            CourtSide::Left
+        } else {
+            CourtSide::Right
+        }
+    }
+
+    pub fn net_top(&self) -> f32 {
+        self.ground_y + self.net_height
+    }
+
+    /// Whether a bounce at `x` is inside the baselines. Lines are in.
+    pub fn in_bounds(&self, x: f32) -> bool {
+        (x - self.net_x).abs() <= self.baseline
+    }
+
+    /// Returns the (min, max) x extent of the service box on the given side.
+    pub fn service_box(&self, side: CourtSide) -> (f32, f32) {
+        match side {
+            CourtSide::Left => (self.net_x - self.service_line, self.net_x),
+            CourtSide::Right => (self.net_x, self.net_x + self.service_line),
+        }
+    }
+}
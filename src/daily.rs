@@ -0,0 +1,247 @@
+//! `--daily` plays the daily challenge. The date picks the scenario, so everyone
+//! gets the same net, ball, gravity, wind and opponent on the same day. Each attempt
+//! is a first-to match against the AI. The best number of points won in an attempt
+//! is kept per day in the profile directory, and the match restarts for a new
+//! attempt right after each one ends.
+
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    ai::AiDifficulty,
+    court::CourtSide,
+    game_state::GameplaySet,
+    hud::{HudElement, HudItem},
+    match_config::{ControllerSlot, MatchConfig, NetVariant},
+    popup::SpawnPopup,
+    scoring::{award_point_system, MatchState, MatchWon, RestartMatch},
+    win_condition::WinConditionKind,
+    PROFILE_DIR,
+};
+
+const DAILY_FILE: &str = "daily_challenge";
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// The scenario for one day. Player 1 takes on the AI.
+#[derive(Clone, Debug)]
+pub struct DailyChallenge {
+    /// `YYYY-MM-DD` in UTC.
+    pub date: String,
+    /// Points that win an attempt, and end it if the AI gets there first.
+    pub target: u32,
+    difficulty: AiDifficulty,
+    net: NetVariant,
+    net_tension: f32,
+    giant_ball: bool,
+    low_gravity: bool,
+    wind: f32,
+}
+
+impl DailyChallenge {
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        Self::for_day(seconds / SECONDS_PER_DAY)
+    }
+
+    // `day` counts from 1970-01-01
+    fn for_day(day: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(day);
+        Self {
+            date: date_name(day),
+            target: rng.gen_range(3..=7),
+            difficulty: [AiDifficulty::Easy, AiDifficulty::Normal, AiDifficulty::Hard]
+                [rng.gen_range(0..3)],
+            net: [NetVariant::Low, NetVariant::Regular, NetVariant::High][rng.gen_range(0..3)],
+            net_tension: rng.gen_range(0.5..=1.0),
+            giant_ball: rng.gen_bool(0.25),
+            low_gravity: rng.gen_bool(0.25),
+            wind: rng.gen_range(-60.0..=60.0),
+        }
+    }
+
+    /// Overrides whatever other flags set, so every attempt plays the same.
+    pub fn apply(&self, config: &mut MatchConfig) {
+        config.modifiers = default();
+        config.modifiers.net = self.net;
+        config.modifiers.giant_ball = self.giant_ball;
+        config.modifiers.low_gravity = self.low_gravity;
+        config.modifiers.wind = self.wind;
+        config.net_tension = self.net_tension;
+        config.win_condition = WinConditionKind::FirstTo(self.target);
+        config.slots[1] = ControllerSlot::Ai(self.difficulty);
+    }
+}
+
+// Civil date of a day counted from 1970-01-01, from Howard Hinnant's date algorithms
+fn date_name(day: u64) -> String {
+    let days = day as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{year:04}-{month:02}-{day_of_month:02}")
+}
+
+#[derive(Resource, Default)]
+struct DailyRun {
+    best: Option<u32>,
+}
+
+fn daily_path() -> PathBuf {
+    PathBuf::from(PROFILE_DIR).join(DAILY_FILE)
+}
+
+// One `<date> <best points>` line per day played
+fn load_best(date: &str) -> Option<u32> {
+    let contents = fs::read_to_string(daily_path()).ok()?;
+    contents.lines().find_map(|line| {
+        let (day, best) = line.split_once(' ')?;
+        if day == date {
+            best.parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+fn save_best(date: &str, best: u32) -> std::io::Result<()> {
+    let contents = fs::read_to_string(daily_path()).unwrap_or_default();
+    let mut lines: Vec<String> = contents
+        .lines()
+        .filter(|line| line.split(' ').next() != Some(date))
+        .map(str::to_string)
+        .collect();
+    lines.push(format!("{date} {best}"));
+    fs::create_dir_all(PROFILE_DIR)?;
+    fs::write(daily_path(), lines.join("\n"))
+}
+
+fn daily_enabled(match_config: Res<MatchConfig>) -> bool {
+    match_config.daily.is_some()
+}
+
+#[derive(Component)]
+struct DailyText;
+
+fn setup_daily_system(
+    mut commands: Commands,
+    match_config: Res<MatchConfig>,
+    mut run: ResMut<DailyRun>,
+) {
+    let Some(challenge) = &match_config.daily else {
+        return;
+    };
+    run.best = load_best(&challenge.date);
+    commands.spawn((
+        DailyText,
+        HudItem(HudElement::DailyChallenge),
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Percent(35.0),
+            ..default()
+        }),
+    ));
+}
+
+fn daily_attempt_system(
+    match_config: Res<MatchConfig>,
+    match_state: Res<MatchState>,
+    mut run: ResMut<DailyRun>,
+    mut match_events: EventReader<MatchWon>,
+    mut restart_events: EventWriter<RestartMatch>,
+    mut popup_events: EventWriter<SpawnPopup>,
+) {
+    let Some(challenge) = &match_config.daily else {
+        return;
+    };
+    for event in match_events.iter() {
+        let result = match_state.points_won(CourtSide::Left);
+        let won = event.side == CourtSide::Left;
+        let new_best = run.best.is_none_or(|best| result > best);
+        if new_best {
+            run.best = Some(result);
+            if let Err(error) = save_best(&challenge.date, result) {
+                warn!("Failed to save the daily challenge result: {error}");
+            }
+        }
+        let text = match (won, new_best) {
+            (true, _) => "Challenge complete!",
+            (false, true) => "New best for today!",
+            (false, false) => "Challenge over",
+        };
+        popup_events.send(SpawnPopup {
+            text: text.to_string(),
+            position: Vec2::ZERO,
+            color: if won { Color::GOLD } else { Color::WHITE },
+        });
+        // Straight into the next attempt
+        restart_events.send(RestartMatch);
+    }
+}
+
+fn update_daily_text_system(
+    match_config: Res<MatchConfig>,
+    match_state: Res<MatchState>,
+    run: Res<DailyRun>,
+    mut query: Query<&mut Text, With<DailyText>>,
+) {
+    let Some(challenge) = &match_config.daily else {
+        return;
+    };
+    if !run.is_changed() && !match_state.is_changed() {
+        return;
+    }
+    let best = run.best.map_or("-".to_string(), |best| {
+        format!("{best}/{}", challenge.target)
+    });
+    query.single_mut().sections[0].value = format!(
+        "Daily challenge {}: first to {}\nYou {} - {} AI  Best today {best}",
+        challenge.date,
+        challenge.target,
+        match_state.points_won(CourtSide::Left),
+        match_state.points_won(CourtSide::Right)
+    );
+}
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DailyRun>()
+            .add_systems(Startup, setup_daily_system.run_if(daily_enabled))
+            .add_systems(
+                FixedUpdate,
+                daily_attempt_system
+                    .after(award_point_system)
+                    .in_set(GameplaySet)
+                    .run_if(daily_enabled),
+            )
+            .add_systems(Update, update_daily_text_system.run_if(daily_enabled));
+    }
+}
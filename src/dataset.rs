@@ -0,0 +1,188 @@
+//! Records human play for imitation learning experiments. With `--record-dataset`
+//! every physics tick writes what each human player saw and did, in the same
+//! observations and actions the agent API uses, to a file under `datasets/`. Nothing
+//! is recorded without the flag. Files carry a random profile ID kept in the profile
+//! directory, so recordings from one machine can be grouped without saying whose
+//! they are.
+//!
+//! A file starts with the bytes `TPDS`, a format version byte, the profile ID as a
+//! little endian u64 and a byte with the number of observation features. Each record
+//! after that is the tick as a little endian u32, the player's side as a byte, 0 for
+//! left, the observation features as little endian f32s and `AgentAction::bits` as a
+//! little endian u16.
+
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    agent::{
+        observe, AgentAction, AgentController, BallObservationQuery, Observation,
+        PlayerObservationQuery,
+    },
+    ai::{auto_move_system, AiController, AutoMove},
+    controller::PlayerInput,
+    court::{Court, CourtSide},
+    game_state::GameplaySet,
+    gamepad::gamepad_controller_system,
+    match_config::MatchConfig,
+    player_movement_system,
+    scoring::MatchState,
+    serve::ServeState,
+    Player, PlayerId, PROFILE_DIR,
+};
+
+const DATASET_DIR: &str = "datasets";
+const PROFILE_ID_FILE: &str = "dataset_id";
+// Version 2 widened the action bits to fit the curve shot, version 3 added the
+// ball's curve to the observation
+const FORMAT_VERSION: u8 = 3;
+// Ten seconds of records between flushes, and the rest when the game quits
+const FLUSH_TICKS: u32 = 600;
+
+#[derive(Resource, Default)]
+struct Dataset {
+    // None until opened, and again if a write fails
+    file: Option<BufWriter<File>>,
+    ticks: u32,
+}
+
+fn dataset_enabled(match_config: Res<MatchConfig>) -> bool {
+    match_config.record_dataset
+}
+
+// Made up the first time a dataset is recorded and reused after that
+fn profile_id() -> std::io::Result<u64> {
+    let path = PathBuf::from(PROFILE_DIR).join(PROFILE_ID_FILE);
+    if let Some(id) = fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| u64::from_str_radix(contents.trim(), 16).ok())
+    {
+        return Ok(id);
+    }
+    let id = rand::random();
+    fs::create_dir_all(PROFILE_DIR)?;
+    fs::write(&path, format!("{id:016x}"))?;
+    Ok(id)
+}
+
+fn create_dataset_file() -> std::io::Result<(PathBuf, BufWriter<File>)> {
+    let profile = profile_id()?;
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    fs::create_dir_all(DATASET_DIR)?;
+    let path = PathBuf::from(DATASET_DIR).join(format!("{profile:016x}-{started}.bin"));
+    let mut file = BufWriter::new(File::create(&path)?);
+    file.write_all(b"TPDS")?;
+    file.write_all(&[FORMAT_VERSION])?;
+    file.write_all(&profile.to_le_bytes())?;
+    file.write_all(&[Observation::FEATURES as u8])?;
+    Ok((path, file))
+}
+
+fn open_dataset_system(mut dataset: ResMut<Dataset>) {
+    match create_dataset_file() {
+        Ok((path, file)) => {
+            info!("Recording a dataset to {}", path.display());
+            dataset.file = Some(file);
+        }
+        Err(error) => warn!("Failed to create a dataset file: {error}"),
+    }
+}
+
+type HumanQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static PlayerId, &'static CourtSide, &'static PlayerInput),
+    (
+        With<Player>,
+        Without<AiController>,
+        Without<AgentController>,
+        Without<AutoMove>,
+    ),
+>;
+
+fn record_dataset_system(
+    court: Res<Court>,
+    match_state: Res<MatchState>,
+    serve_state: Res<ServeState>,
+    mut dataset: ResMut<Dataset>,
+    ball_query: BallObservationQuery,
+    player_query: PlayerObservationQuery,
+    // Only players a human moves themselves
+    human_query: HumanQuery,
+) {
+    dataset.ticks += 1;
+    let tick = dataset.ticks;
+    let Some(file) = dataset.file.as_mut() else {
+        return;
+    };
+    let mut result = Ok(());
+    for (id, side, input) in &human_query {
+        let side = *side;
+        let observation = observe(
+            &court,
+            &match_state,
+            &serve_state,
+            &ball_query,
+            &player_query,
+            id,
+        );
+        let action = AgentAction::from_input(input, side);
+        result = result
+            .and_then(|_| file.write_all(&tick.to_le_bytes()))
+            .and_then(|_| file.write_all(&[(side == CourtSide::Right) as u8]))
+            .and_then(|_| {
+                observation
+                    .features()
+                    .iter()
+                    .try_for_each(|feature| file.write_all(&feature.to_le_bytes()))
+            })
+            .and_then(|_| file.write_all(&action.bits().to_le_bytes()));
+    }
+    if tick.is_multiple_of(FLUSH_TICKS) {
+        result = result.and_then(|_| file.flush());
+    }
+    if let Err(error) = result {
+        warn!("Failed to write the dataset, recording stopped: {error}");
+        dataset.file = None;
+    }
+}
+
+// The game is quit without dropping its resources, so the buffer has to be written
+// out here
+fn flush_dataset_system(mut dataset: ResMut<Dataset>, mut exit_events: EventReader<AppExit>) {
+    if exit_events.iter().count() == 0 {
+        return;
+    }
+    if let Some(file) = dataset.file.as_mut() {
+        if let Err(error) = file.flush() {
+            warn!("Failed to write the end of the dataset: {error}");
+        }
+    }
+}
+
+pub struct DatasetPlugin;
+
+impl Plugin for DatasetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Dataset>()
+            .add_systems(Startup, open_dataset_system.run_if(dataset_enabled))
+            .add_systems(
+                FixedUpdate,
+                record_dataset_system
+                    .after(gamepad_controller_system)
+                    .after(auto_move_system)
+                    .before(player_movement_system)
+                    .in_set(GameplaySet)
+                    .run_if(dataset_enabled),
+            )
+            .add_systems(Last, flush_dataset_system.run_if(dataset_enabled));
+    }
+}
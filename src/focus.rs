@@ -0,0 +1,48 @@
+//! Keeps the game from burning battery in the background: losing window focus pauses
+//! the simulation and drops winit into low power mode, so frames are only presented
+//! when something wakes the event loop. Matches without a human player, like soak
+//! runs and agent competitions, are left unattended and keep going.
+
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    window::WindowFocused,
+    winit::{UpdateMode, WinitSettings},
+};
+
+use crate::match_config::MatchConfig;
+
+const UNFOCUSED_MAX_WAIT: Duration = Duration::from_millis(250);
+
+#[derive(Resource)]
+pub struct WindowFocus(bool);
+
+fn track_window_focus_system(
+    mut events: EventReader<WindowFocused>,
+    mut window_focus: ResMut<WindowFocus>,
+) {
+    for event in events.iter() {
+        window_focus.0 = event.focused;
+    }
+}
+
+pub fn window_focused(window_focus: Res<WindowFocus>, match_config: Res<MatchConfig>) -> bool {
+    window_focus.0 || match_config.unattended()
+}
+
+pub struct FocusPlugin;
+
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WindowFocus(true))
+            .insert_resource(WinitSettings {
+                focused_mode: UpdateMode::Continuous,
+                unfocused_mode: UpdateMode::ReactiveLowPower {
+                    max_wait: UNFOCUSED_MAX_WAIT,
+                },
+                ..default()
+            })
+            .add_systems(PreUpdate, track_window_focus_system);
+    }
+}
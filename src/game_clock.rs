@@ -0,0 +1,69 @@
+//! Gameplay time. Anything that counts down during a match should use the clock's delta
+//! instead of `TIME_STEP`, so that it stops while paused and slows down with the game.
+//! The pause menu pauses the clock, and the `timescale` console command scales it.
+//! Physics integration still steps by `TIME_STEP`.
+
+use bevy::prelude::*;
+
+use crate::{console::ConsoleAppExt, TIME_STEP};
+
+#[derive(Resource, Reflect, Clone)]
+#[reflect(Resource)]
+pub struct GameClock {
+    pub paused: bool,
+    /// Gameplay seconds per real second, 1.0 at normal speed.
+    pub scale: f32,
+    delta: f32,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            scale: 1.0,
+            delta: 0.0,
+        }
+    }
+}
+
+impl GameClock {
+    /// Gameplay seconds that passed this tick, zero while paused.
+    pub fn delta(&self) -> f32 {
+        self.delta
+    }
+}
+
+pub fn advance_game_clock_system(mut clock: ResMut<GameClock>) {
+    clock.delta = if clock.paused {
+        0.0
+    } else {
+        TIME_STEP * clock.scale
+    };
+}
+
+fn timescale_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let mut clock = world.resource_mut::<GameClock>();
+    match args {
+        [] => {}
+        [scale] => match scale.parse::<f32>() {
+            Ok(scale) if scale >= 0.0 => clock.scale = scale,
+            _ => return Err(format!("'{scale}' is not a time scale")),
+        },
+        _ => return Err("usage: timescale [scale]".to_string()),
+    }
+    Ok(format!("time scale {}", clock.scale))
+}
+
+pub struct GameClockPlugin;
+
+impl Plugin for GameClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameClock>()
+            .register_type::<GameClock>()
+            .add_console_command(
+                "timescale",
+                "timescale [scale], show or set the speed of gameplay timers",
+                timescale_command,
+            );
+    }
+}
@@ -0,0 +1,130 @@
+//! Where the game is at, as a Bevy state. During a match it follows the serve and
+//! scoring resources: waiting on the serve, the rally, the pause between points and
+//! the finished match. `Menu` and `Paused` take the game out of play, and gameplay
+//! systems only run while `in_gameplay` holds.
+
+use bevy::prelude::*;
+
+use crate::{
+    catch_up::{within_catch_up_limit, CatchUpSet},
+    console::ConsoleAppExt,
+    focus::window_focused,
+    scoring::{MatchState, PointOver},
+    serve::ServeState,
+    Ball, BallState,
+};
+
+#[derive(States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum GameState {
+    // Nothing of the match runs
+    Menu,
+    // From the start of the point until the serve is hit
+    #[default]
+    Serving,
+    // The ball is in play
+    Rally,
+    // Point decided, waiting to serve the next one
+    PointOver,
+    // The match is frozen where it was left
+    Paused,
+    // Won, the ball stays dead until a new match starts
+    MatchOver,
+}
+
+impl GameState {
+    /// The states a match is being played in.
+    pub fn is_gameplay(self) -> bool {
+        matches!(
+            self,
+            GameState::Serving | GameState::Rally | GameState::PointOver | GameState::MatchOver
+        )
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "menu" => GameState::Menu,
+            "serving" => GameState::Serving,
+            "rally" => GameState::Rally,
+            "pointover" => GameState::PointOver,
+            "paused" => GameState::Paused,
+            "matchover" => GameState::MatchOver,
+            _ => return None,
+        })
+    }
+}
+
+/// The FixedUpdate systems that play the match. They only run during gameplay, with
+/// the window focused, and on ticks within the catch-up limit.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct GameplaySet;
+
+pub fn in_gameplay(state: Res<State<GameState>>) -> bool {
+    state.get().is_gameplay()
+}
+
+fn sync_game_state_system(
+    state: Res<State<GameState>>,
+    serve_state: Res<ServeState>,
+    point_over: Res<PointOver>,
+    match_state: Res<MatchState>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let phase = if match_state.is_over() {
+        GameState::MatchOver
+    } else if point_over.is_over() {
+        GameState::PointOver
+    } else if matches!(*serve_state, ServeState::Ready | ServeState::Tossed { .. }) {
+        GameState::Serving
+    } else {
+        GameState::Rally
+    };
+    if *state.get() != phase {
+        next_state.set(phase);
+    }
+}
+
+// `state` shows where the game is at, `state <name>` moves it there. During a match the
+// gameplay states follow the serve and scoring again on the next frame.
+fn state_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    match args {
+        [] => {}
+        [name] => {
+            let Some(state) = GameState::parse(name) else {
+                return Err(format!(
+                    "unknown state '{name}'\nstates: menu serving rally pointover paused matchover"
+                ));
+            };
+            world.resource_mut::<NextState<GameState>>().set(state);
+            return Ok(format!("game {state:?}"));
+        }
+        _ => return Err("usage: state [<name>]".to_string()),
+    }
+
+    let ball_state = world
+        .query_filtered::<&BallState, With<Ball>>()
+        .get_single(world)
+        .map_or("none".to_string(), |state| format!("{state:?}"));
+    Ok(format!(
+        "game {:?}\nserve {:?}\nball {ball_state}",
+        world.resource::<State<GameState>>().get(),
+        world.resource::<ServeState>(),
+    ))
+}
+
+pub struct GameStatePlugin;
+
+impl Plugin for GameStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<GameState>()
+            .configure_set(
+                FixedUpdate,
+                GameplaySet
+                    .after(CatchUpSet)
+                    .run_if(window_focused)
+                    .run_if(in_gameplay)
+                    .run_if(within_catch_up_limit),
+            )
+            .add_console_command("state", "state [<name>]", state_command)
+            .add_systems(Update, sync_game_state_system.run_if(in_gameplay));
+    }
+}
@@ -0,0 +1,152 @@
+//! Gamepad controls for the human players. A gamepad drives the same `PlayerInput` as
+//! the keys, and a player with both a keyboard layout and a pad can use either. Newly
+//! connected pads go to the first human player without one, and `pad <player> <id>`
+//! reassigns them.
+
+use bevy::{
+    input::gamepad::{GamepadConnection, GamepadConnectionEvent},
+    prelude::*,
+};
+
+use crate::{
+    agent::AgentController, ai::AiController, console::ConsoleAppExt, controller::PlayerInput,
+    PlayerId,
+};
+
+const STICK_DEADZONE: f32 = 0.5;
+
+/// Which gamepad each player uses, indexed by `PlayerId`.
+#[derive(Resource, Default)]
+pub struct GamepadAssignments(Vec<Option<Gamepad>>);
+
+impl GamepadAssignments {
+    pub fn get(&self, player: PlayerId) -> Option<Gamepad> {
+        self.0.get(player.0).copied().flatten()
+    }
+
+    pub fn assign(&mut self, player: PlayerId, gamepad: Option<Gamepad>) {
+        if self.0.len() <= player.0 {
+            self.0.resize(player.0 + 1, None);
+        }
+        // A pad can only drive one player at a time
+        for assigned in &mut self.0 {
+            if gamepad.is_some() && *assigned == gamepad {
+                *assigned = None;
+            }
+        }
+        self.0[player.0] = gamepad;
+    }
+}
+
+fn gamepad_hot_plug_system(
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+    mut assignments: ResMut<GamepadAssignments>,
+    player_query: Query<&PlayerId, (Without<AiController>, Without<AgentController>)>,
+) {
+    for event in connection_events.iter() {
+        match event.connection {
+            GamepadConnection::Connected(_) => {
+                let mut players: Vec<PlayerId> = player_query.iter().copied().collect();
+                players.sort_by_key(|player| player.0);
+                if let Some(player) = players
+                    .into_iter()
+                    .find(|player| assignments.get(*player).is_none())
+                {
+                    assignments.assign(player, Some(event.gamepad));
+                    info!(
+                        "Gamepad {} assigned to player {}",
+                        event.gamepad.id,
+                        player.0 + 1
+                    );
+                }
+            }
+            GamepadConnection::Disconnected => {
+                for assigned in &mut assignments.0 {
+                    if *assigned == Some(event.gamepad) {
+                        *assigned = None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+type GamepadPlayerQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static PlayerId, &'static mut PlayerInput),
+    (Without<AiController>, Without<AgentController>),
+>;
+
+pub fn gamepad_controller_system(
+    assignments: Res<GamepadAssignments>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut query: GamepadPlayerQuery,
+) {
+    for (player, mut input) in &mut query {
+        let Some(gamepad) = assignments.get(*player) else {
+            continue;
+        };
+        let button = |button_type| GamepadButton::new(gamepad, button_type);
+        let stick = |axis_type| {
+            axes.get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or_default()
+        };
+        let stick_x = stick(GamepadAxisType::LeftStickX);
+        let stick_y = stick(GamepadAxisType::LeftStickY);
+
+        input.left |=
+            stick_x < -STICK_DEADZONE || buttons.pressed(button(GamepadButtonType::DPadLeft));
+        input.right |=
+            stick_x > STICK_DEADZONE || buttons.pressed(button(GamepadButtonType::DPadRight));
+        input.jump |= buttons.pressed(button(GamepadButtonType::South));
+        input.jump_pressed |= buttons.just_pressed(button(GamepadButtonType::South));
+        input.swing_pressed |= buttons.just_pressed(button(GamepadButtonType::West))
+            || buttons.just_pressed(button(GamepadButtonType::RightTrigger));
+        input.dash_pressed |= buttons.just_pressed(button(GamepadButtonType::East))
+            || buttons.just_pressed(button(GamepadButtonType::LeftTrigger));
+        input.dive_pressed |= buttons.just_pressed(button(GamepadButtonType::RightTrigger2));
+        input.lob |= buttons.pressed(button(GamepadButtonType::North));
+        input.curve |= buttons.pressed(button(GamepadButtonType::LeftTrigger2));
+        input.drop |=
+            stick_y < -STICK_DEADZONE || buttons.pressed(button(GamepadButtonType::DPadDown));
+    }
+}
+
+fn pad_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [player, id] = args else {
+        return Err("usage: pad <player> <gamepad id|none>".to_string());
+    };
+    let player = match player.parse::<usize>() {
+        Ok(number) if number > 0 => PlayerId(number - 1),
+        _ => return Err(format!("'{player}' is not a player number")),
+    };
+    let gamepad = match *id {
+        "none" => None,
+        id => {
+            let id = id
+                .parse()
+                .map_err(|_| format!("'{id}' is not a gamepad id"))?;
+            let gamepad = Gamepad::new(id);
+            if !world.resource::<Gamepads>().contains(gamepad) {
+                return Err(format!("gamepad {id} is not connected"));
+            }
+            Some(gamepad)
+        }
+    };
+    world
+        .resource_mut::<GamepadAssignments>()
+        .assign(player, gamepad);
+    Ok(format!("player {} uses gamepad {id}", player.0 + 1))
+}
+
+pub struct GamepadInputPlugin;
+
+impl Plugin for GamepadInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GamepadAssignments>()
+            .add_console_command("pad", "pad <player> <gamepad id|none>", pad_command)
+            .add_systems(PreUpdate, gamepad_hot_plug_system);
+    }
+}
@@ -0,0 +1,238 @@
+//! What the in-match HUD shows. Every HUD element is tagged with a `HudItem` and
+//! hidden or shown to match `HudSettings`, which the `hud` console command changes
+//! and saves to the profile. `hud full|minimal|off` picks a preset, `hud <element>
+//! on|off` toggles a single element. This module also owns the rally counter and
+//! the speed radar, which shows how fast the last hit left the racket.
+
+use std::{collections::HashSet, fs, path::PathBuf};
+
+use bevy::prelude::*;
+
+use crate::{
+    console::ConsoleAppExt,
+    racket::{HitConfirmed, Rally},
+    PROFILE_DIR,
+};
+
+const HUD_FILE: &str = "hud";
+const HUD_FONT_SIZE: f32 = 18.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum HudElement {
+    Score,
+    RallyCounter,
+    SpeedRadar,
+    StaminaBars,
+    RotationQueue,
+    DailyChallenge,
+}
+
+impl HudElement {
+    const ALL: [HudElement; 6] = [
+        HudElement::Score,
+        HudElement::RallyCounter,
+        HudElement::SpeedRadar,
+        HudElement::StaminaBars,
+        HudElement::RotationQueue,
+        HudElement::DailyChallenge,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            HudElement::Score => "score",
+            HudElement::RallyCounter => "rally",
+            HudElement::SpeedRadar => "radar",
+            HudElement::StaminaBars => "stamina",
+            HudElement::RotationQueue => "queue",
+            HudElement::DailyChallenge => "daily",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|element| element.name() == name)
+    }
+}
+
+/// Marks the root UI node of a HUD element.
+#[derive(Component)]
+pub struct HudItem(pub HudElement);
+
+#[derive(Resource, Default)]
+pub struct HudSettings {
+    hidden: HashSet<HudElement>,
+}
+
+impl HudSettings {
+    fn path() -> PathBuf {
+        PathBuf::from(PROFILE_DIR).join(HUD_FILE)
+    }
+
+    // The file lists the hidden elements, one per line
+    fn load() -> Self {
+        let hidden = fs::read_to_string(Self::path())
+            .map(|contents| contents.lines().filter_map(HudElement::from_name).collect())
+            .unwrap_or_default();
+        Self { hidden }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let names: Vec<&str> = HudElement::ALL
+            .into_iter()
+            .filter(|element| self.hidden.contains(element))
+            .map(HudElement::name)
+            .collect();
+        fs::create_dir_all(PROFILE_DIR)?;
+        fs::write(Self::path(), names.join("\n"))
+    }
+
+    fn set_preset(&mut self, preset: &str) -> bool {
+        self.hidden = match preset {
+            "full" => HashSet::new(),
+            // Only the score, the least a match needs
+            "minimal" => HudElement::ALL
+                .into_iter()
+                .filter(|element| *element != HudElement::Score)
+                .collect(),
+            "off" => HudElement::ALL.into_iter().collect(),
+            _ => return false,
+        };
+        true
+    }
+
+    /// The preset the settings match, or "custom" after toggling single elements.
+    pub fn preset_name(&self) -> &'static str {
+        ["full", "minimal", "off"]
+            .into_iter()
+            .find(|preset| {
+                let mut settings = HudSettings::default();
+                settings.set_preset(preset);
+                settings.hidden == self.hidden
+            })
+            .unwrap_or("custom")
+    }
+
+    /// Full, then minimal, then off, then full again.
+    pub fn cycle_preset(&mut self) {
+        let next = match self.preset_name() {
+            "full" => "minimal",
+            "minimal" => "off",
+            _ => "full",
+        };
+        self.set_preset(next);
+    }
+}
+
+#[derive(Component)]
+struct RallyCounterText;
+
+#[derive(Component)]
+struct SpeedRadarText;
+
+fn hud_text(element: HudElement, bottom: f32) -> (HudItem, TextBundle) {
+    (
+        HudItem(element),
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: HUD_FONT_SIZE,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(bottom),
+            left: Val::Percent(45.0),
+            ..default()
+        }),
+    )
+}
+
+fn setup_hud_system(mut commands: Commands) {
+    commands.spawn((RallyCounterText, hud_text(HudElement::RallyCounter, 36.0)));
+    commands.spawn((SpeedRadarText, hud_text(HudElement::SpeedRadar, 12.0)));
+}
+
+fn update_rally_counter_system(
+    rally: Res<Rally>,
+    mut query: Query<&mut Text, With<RallyCounterText>>,
+) {
+    if rally.is_changed() {
+        query.single_mut().sections[0].value = format!("Rally {}", rally.returns());
+    }
+}
+
+fn update_speed_radar_system(
+    mut hit_events: EventReader<HitConfirmed>,
+    mut query: Query<&mut Text, With<SpeedRadarText>>,
+) {
+    for event in hit_events.iter() {
+        query.single_mut().sections[0].value = format!("Speed {:.0}", event.speed);
+    }
+}
+
+fn apply_hud_visibility_system(
+    settings: Res<HudSettings>,
+    mut query: Query<(&HudItem, &mut Visibility)>,
+) {
+    for (item, mut visibility) in &mut query {
+        let wanted = if settings.hidden.contains(&item.0) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        visibility.set_if_neq(wanted);
+    }
+}
+
+fn hud_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let mut settings = world.resource_mut::<HudSettings>();
+    match args {
+        [preset] => {
+            if !settings.set_preset(preset) {
+                return Err(format!("unknown preset '{preset}'"));
+            }
+        }
+        [element, state] => {
+            let element = HudElement::from_name(element)
+                .ok_or_else(|| format!("unknown HUD element '{element}'"))?;
+            match *state {
+                "on" => settings.hidden.remove(&element),
+                "off" => settings.hidden.insert(element),
+                _ => return Err(format!("expected on or off, got '{state}'")),
+            };
+        }
+        _ => {
+            return Err(
+                "usage: hud full|minimal|off, or hud score|rally|radar|stamina|queue|daily on|off"
+                    .to_string(),
+            )
+        }
+    }
+    settings
+        .save()
+        .map_err(|error| format!("changed but not saved: {error}"))?;
+    Ok("HUD updated".to_string())
+}
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HudSettings::load())
+            .add_systems(Startup, setup_hud_system)
+            .add_systems(
+                Update,
+                (
+                    update_rally_counter_system,
+                    update_speed_radar_system,
+                    apply_hud_visibility_system,
+                ),
+            )
+            .add_console_command(
+                "hud",
+                "hud full|minimal|off, or hud score|rally|radar|stamina|queue|daily on|off",
+                hud_command,
+            );
+    }
+}
@@ -0,0 +1,314 @@
+//! King of the court, a casual mode for three or more players on one machine.
+//! `--king-of-the-court` turns it on, with the players past the second given as
+//! `--p3=<controller>`, `--p4=<controller>` and so on. Two players are on court at a
+//! time. Whoever loses a point goes to the back of the queue, and the first player
+//! in the queue takes their side when the next point starts. The match never ends,
+//! and each player's run of points won in a row is kept as their streak.
+//!
+//! Court sides keep their `PlayerId`, so the rest of the game sees the same two
+//! players. Rotating swaps the player entity on the losing side for a new one.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    ai::AutoMove,
+    character::{swap_character_system, CharacterDef, Characters, SelectedCharacter},
+    court::CourtSide,
+    game_state::GameplaySet,
+    gamepad::GamepadAssignments,
+    hud::{HudElement, HudItem},
+    match_config::{ControllerSlot, MatchConfig},
+    popup::SpawnPopup,
+    scoring::{award_point_system, next_point_system, PointOver, PointWon, RestartMatch},
+    spawn_player,
+    win_condition::WinConditionKind,
+    Player, PlayerId,
+};
+
+struct Contestant {
+    slot: ControllerSlot,
+    auto_move: bool,
+    tint: Color,
+    streak: u32,
+    best_streak: u32,
+}
+
+#[derive(Resource, Default)]
+struct Rotation {
+    // In player number order, player 1 first
+    contestants: Vec<Contestant>,
+    // Who plays on each side, left first, as indices into `contestants`
+    on_court: [usize; 2],
+    queue: VecDeque<usize>,
+    // The side that lost the last point, swapped out once the next one starts
+    rotating_out: Option<CourtSide>,
+}
+
+fn king_of_the_court_enabled(match_config: Res<MatchConfig>) -> bool {
+    match_config.win_condition == WinConditionKind::KingOfTheCourt
+}
+
+#[derive(Component)]
+struct QueueText;
+
+fn setup_rotation_system(
+    mut commands: Commands,
+    match_config: Res<MatchConfig>,
+    characters: Res<Characters>,
+    selected_character: Res<SelectedCharacter>,
+    mut rotation: ResMut<Rotation>,
+) {
+    let def = selected_character.def(&characters);
+    let slots = match_config.slots.iter().chain(&match_config.challengers);
+    rotation.contestants = slots
+        .enumerate()
+        .map(|(number, slot)| Contestant {
+            slot: *slot,
+            auto_move: match_config.auto_move.get(number).copied().unwrap_or(false),
+            // The first two match what setup gave the players already on court
+            tint: def.palette(selected_character.palette + number),
+            streak: 0,
+            best_streak: 0,
+        })
+        .collect();
+    rotation.on_court = [0, 1];
+    rotation.queue = (2..rotation.contestants.len()).collect();
+
+    commands.spawn((
+        QueueText,
+        HudItem(HudElement::RotationQueue),
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(16.0),
+            ..default()
+        }),
+    ));
+}
+
+fn track_streaks_system(mut rotation: ResMut<Rotation>, mut point_events: EventReader<PointWon>) {
+    for event in point_events.iter() {
+        let winner = rotation.on_court[event.side.index()];
+        let loser = rotation.on_court[event.side.opposite().index()];
+        let contestant = &mut rotation.contestants[winner];
+        contestant.streak += 1;
+        contestant.best_streak = contestant.best_streak.max(contestant.streak);
+        rotation.contestants[loser].streak = 0;
+        if !rotation.queue.is_empty() {
+            rotation.rotating_out = Some(event.side.opposite());
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn rotate_players_system(
+    mut commands: Commands,
+    match_config: Res<MatchConfig>,
+    characters: Res<Characters>,
+    selected_character: Res<SelectedCharacter>,
+    point_over: Res<PointOver>,
+    mut rotation: ResMut<Rotation>,
+    mut gamepad_assignments: ResMut<GamepadAssignments>,
+    mut popup_events: EventWriter<SpawnPopup>,
+    player_query: Query<(Entity, &PlayerId, &Handle<TextureAtlas>), With<Player>>,
+) {
+    if point_over.is_over() {
+        return;
+    }
+    let Some(side) = rotation.rotating_out.take() else {
+        return;
+    };
+    let Some(incoming) = rotation.queue.pop_front() else {
+        return;
+    };
+    let outgoing = rotation.on_court[side.index()];
+    rotation.queue.push_back(outgoing);
+    rotation.on_court[side.index()] = incoming;
+
+    let contestant = &rotation.contestants[incoming];
+    let swapped = bring_on(
+        &mut commands,
+        &mut gamepad_assignments,
+        selected_character.def(&characters),
+        match_config.modifiers.player_scale(),
+        contestant,
+        side,
+        &player_query,
+    );
+    if swapped {
+        popup_events.send(SpawnPopup {
+            text: format!("P{} is on", incoming + 1),
+            position: Vec2::ZERO,
+            color: contestant.tint,
+        });
+    }
+}
+
+// Swaps the player entity on `side` for a new one played by `contestant`. False if
+// there was no player there to swap.
+fn bring_on(
+    commands: &mut Commands,
+    gamepad_assignments: &mut GamepadAssignments,
+    character: &CharacterDef,
+    player_scale: f32,
+    contestant: &Contestant,
+    side: CourtSide,
+    player_query: &Query<(Entity, &PlayerId, &Handle<TextureAtlas>), With<Player>>,
+) -> bool {
+    let id = PlayerId(side.index());
+    let Some((entity, _, texture_atlas)) =
+        player_query.iter().find(|(_, player, _)| **player == id)
+    else {
+        return false;
+    };
+    let texture_atlas = texture_atlas.clone();
+    commands.entity(entity).despawn();
+    // The outgoing player's pad must not drive whoever comes on
+    gamepad_assignments.assign(id, None);
+    let player = spawn_player(
+        commands,
+        gamepad_assignments,
+        side,
+        character,
+        texture_atlas,
+        contestant.tint,
+        player_scale,
+        contestant.slot,
+    );
+    if contestant.auto_move {
+        commands.entity(player).insert(AutoMove);
+    }
+    true
+}
+
+// A restarted match starts over from the first two players in player order, with the
+// rest queued behind them and no streaks
+#[allow(clippy::too_many_arguments)]
+fn restart_rotation_system(
+    mut commands: Commands,
+    match_config: Res<MatchConfig>,
+    characters: Res<Characters>,
+    selected_character: Res<SelectedCharacter>,
+    mut rotation: ResMut<Rotation>,
+    mut gamepad_assignments: ResMut<GamepadAssignments>,
+    mut restart_events: EventReader<RestartMatch>,
+    player_query: Query<(Entity, &PlayerId, &Handle<TextureAtlas>), With<Player>>,
+) {
+    if restart_events.iter().count() == 0 {
+        return;
+    }
+
+    // The players screen may have handed the slots to other controllers
+    let slots = match_config.slots.iter().chain(&match_config.challengers);
+    for (contestant, slot) in rotation.contestants.iter_mut().zip(slots) {
+        contestant.slot = *slot;
+        contestant.streak = 0;
+        contestant.best_streak = 0;
+    }
+    rotation.queue = (2..rotation.contestants.len()).collect();
+    rotation.rotating_out = None;
+    for side in [CourtSide::Left, CourtSide::Right] {
+        if rotation.on_court[side.index()] == side.index() {
+            continue;
+        }
+        rotation.on_court[side.index()] = side.index();
+        bring_on(
+            &mut commands,
+            &mut gamepad_assignments,
+            selected_character.def(&characters),
+            match_config.modifiers.player_scale(),
+            &rotation.contestants[side.index()],
+            side,
+            &player_query,
+        );
+    }
+}
+
+// Picking another character gives every contestant that character's palette for
+// their number, and the players on court are recoloured after the swap did its own
+fn retint_contestants_system(
+    characters: Res<Characters>,
+    selected_character: Res<SelectedCharacter>,
+    mut rotation: ResMut<Rotation>,
+    mut player_query: Query<(&PlayerId, &mut TextureAtlasSprite), With<Player>>,
+) {
+    if !selected_character.is_changed() || selected_character.is_added() {
+        return;
+    }
+    let def = selected_character.def(&characters);
+    for (number, contestant) in rotation.contestants.iter_mut().enumerate() {
+        contestant.tint = def.palette(selected_character.palette + number);
+    }
+    for (id, mut sprite) in &mut player_query {
+        sprite.color = rotation.contestants[rotation.on_court[id.0]].tint;
+    }
+}
+
+fn update_queue_text_system(rotation: Res<Rotation>, mut query: Query<&mut Text, With<QueueText>>) {
+    if !rotation.is_changed() {
+        return;
+    }
+    let [left, right] = rotation.on_court.map(|index| {
+        format!(
+            "P{} streak {}",
+            index + 1,
+            rotation.contestants[index].streak
+        )
+    });
+    let queue: Vec<String> = rotation
+        .queue
+        .iter()
+        .map(|index| format!("P{}", index + 1))
+        .collect();
+    let best: Vec<String> = rotation
+        .contestants
+        .iter()
+        .enumerate()
+        .map(|(index, contestant)| format!("P{} {}", index + 1, contestant.best_streak))
+        .collect();
+    query.single_mut().sections[0].value = format!(
+        "{left}  vs  {right}\nUp next: {}\nBest streaks: {}",
+        queue.join(" "),
+        best.join("  ")
+    );
+}
+
+pub struct KingOfTheCourtPlugin;
+
+impl Plugin for KingOfTheCourtPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rotation>()
+            .add_systems(
+                Startup,
+                setup_rotation_system.run_if(king_of_the_court_enabled),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    track_streaks_system.after(award_point_system),
+                    rotate_players_system.after(next_point_system),
+                )
+                    .in_set(GameplaySet)
+                    .run_if(king_of_the_court_enabled),
+            )
+            .add_systems(
+                Update,
+                (
+                    restart_rotation_system,
+                    retint_contestants_system.after(swap_character_system),
+                    update_queue_text_system,
+                )
+                    .run_if(king_of_the_court_enabled),
+            );
+    }
+}
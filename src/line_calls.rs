@@ -0,0 +1,103 @@
+//! Line calls for rally shots. The first bounce of every shot is checked against the
+//! court lines in `Court`. An "OUT!" call goes up where an out ball landed, and close
+//! calls that stayed in get an "IN".
+//! Serves are judged against the service box by the serve module instead.
+
+use bevy::prelude::*;
+
+use crate::{
+    ball_collision_response_system,
+    court::{Court, CourtSide},
+    game_state::GameplaySet,
+    popup::SpawnPopup,
+    serve::ServeState,
+    Ball, Bounces, Net, SolidCollisionEvent,
+};
+
+// Balls landing in this close to the baseline get called in out loud
+const CLOSE_CALL_DISTANCE: f32 = 24.0;
+
+/// A shot landed inside the lines.
+#[derive(Event)]
+pub struct BallIn {
+    pub position: Vec2,
+}
+
+/// A shot landed past the baseline on `side`.
+#[derive(Event)]
+pub struct BallOut {
+    pub side: CourtSide,
+    pub position: Vec2,
+}
+
+pub fn line_call_system(
+    court: Res<Court>,
+    serve_state: Res<ServeState>,
+    net_query: Query<(), With<Net>>,
+    ball_query: Query<(&Transform, &Bounces), With<Ball>>,
+    mut collision_events: EventReader<SolidCollisionEvent<Ball>>,
+    mut in_events: EventWriter<BallIn>,
+    mut out_events: EventWriter<BallOut>,
+) {
+    for event in collision_events.iter() {
+        if *serve_state != ServeState::InPlay {
+            continue;
+        }
+        let Some(solid) = event.collided_y else {
+            continue;
+        };
+        let (transform, bounces) = ball_query.get(event.collider).unwrap();
+        // Only the first bounce on a side is called, the net cord isn't a bounce
+        if net_query.contains(solid) || bounces.count != 1 {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let side = court.side_of(position.x);
+        if court.in_bounds(position.x) {
+            in_events.send(BallIn { position });
+        } else {
+            out_events.send(BallOut { side, position });
+        }
+    }
+}
+
+fn call_out_system(
+    court: Res<Court>,
+    mut in_events: EventReader<BallIn>,
+    mut out_events: EventReader<BallOut>,
+    mut popup_events: EventWriter<SpawnPopup>,
+) {
+    for event in in_events.iter() {
+        let to_baseline = court.baseline - (event.position.x - court.net_x).abs();
+        if to_baseline <= CLOSE_CALL_DISTANCE {
+            popup_events.send(SpawnPopup {
+                text: "IN".to_string(),
+                position: event.position,
+                color: Color::GREEN,
+            });
+        }
+    }
+    for event in out_events.iter() {
+        popup_events.send(SpawnPopup {
+            text: "OUT!".to_string(),
+            position: event.position,
+            color: Color::RED,
+        });
+    }
+}
+
+pub struct LineCallsPlugin;
+
+impl Plugin for LineCallsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BallIn>()
+            .add_event::<BallOut>()
+            .add_systems(
+                FixedUpdate,
+                line_call_system
+                    .after(ball_collision_response_system)
+                    .in_set(GameplaySet),
+            )
+            .add_systems(Update, call_out_system);
+    }
+}
@@ -1,13 +1,110 @@
-use std::{cmp::Ordering, marker::PhantomData};
+use std::{cmp::Ordering, marker::PhantomData, time::Duration};
 
-use bevy::{prelude::*, sprite::collide_aabb::collide, window::PrimaryWindow};
+use agent::{agent_controller_system, AgentController};
+use ai::{ai_controller_system, auto_move_system, AiController, AutoMove};
+use ball_trail::BallTrailPlugin;
+use bevy::{
+    app::ScheduleRunnerPlugin,
+    ecs::system::EntityCommands,
+    prelude::*,
+    render::{settings::WgpuSettings, RenderPlugin},
+    sprite::collide_aabb::collide,
+    window::{ExitCondition, PrimaryWindow, WindowResolution},
+    winit::WinitPlugin,
+};
+use bug_report::BugReportPlugin;
+use catch_up::CatchUpPlugin;
+use character::{
+    load_character_atlas, AnimationLibrary, CharacterDef, CharacterPlugin, Characters,
+    SelectedCharacter,
+};
+use cheats::CheatsPlugin;
+use console::{console_closed, system_enabled, ConsoleAppExt, ConsolePlugin};
+use controller::{
+    keyboard_controller_system, reset_player_input_system, ControllerPlugin, KeyboardController,
+    PlayerInput,
+};
+use court::{Court, CourtSide, BASELINE_DISTANCE, NET_THICKNESS, SERVICE_LINE_DISTANCE};
+use daily::DailyPlugin;
+use dataset::DatasetPlugin;
+use focus::FocusPlugin;
+use game_clock::{advance_game_clock_system, GameClock, GameClockPlugin};
+use game_state::{GameStatePlugin, GameplaySet};
+use gamepad::{gamepad_controller_system, GamepadAssignments, GamepadInputPlugin};
+use hud::HudPlugin;
+use king_of_the_court::KingOfTheCourtPlugin;
+use line_calls::LineCallsPlugin;
+use match_config::{ControllerSlot, MatchConfig};
+use match_setup::MatchSetupPlugin;
+use pause::PausePlugin;
+use physics_guard::PhysicsGuardPlugin;
+use popup::PopupPlugin;
+use racket::{
+    ball_hit_response_system, confirm_hit_system, escalation_tint_system, facing,
+    perfect_hit_popup_system, racket_hit_system, reset_rally_system, swing_racket_system,
+    BallHitEvent, HitConfirmed, PerfectHit, Racket, Rally, SwingPhase,
+};
+use scoring::{award_point_system, ScoringPlugin};
+use serve::ServePlugin;
+use serve_debug::ServeDebugPlugin;
+use smash_zone::SmashZonePlugin;
+use soak::SoakPlugin;
+use stamina::{
+    InfiniteStamina, Stamina, StaminaPlugin, DASH_COST, DIVE_COST, JUMP_COST, RUN_DRAIN,
+};
+use tutorial::TutorialPlugin;
+
+mod agent;
+mod ai;
+mod ball_trail;
+mod bug_report;
+mod catch_up;
+mod character;
+mod cheats;
+mod console;
+mod controller;
+mod court;
+mod daily;
+mod dataset;
+mod focus;
+mod game_clock;
+mod game_state;
+mod gamepad;
+mod hud;
+mod king_of_the_court;
+mod line_calls;
+mod match_config;
+mod match_setup;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod pause;
+mod physics_guard;
+mod popup;
+mod racket;
+mod scoring;
+mod serve;
+mod serve_debug;
+mod smash_zone;
+mod soak;
+mod stamina;
+mod trajectory;
+mod tutorial;
+mod win_condition;
 
 #[derive(Component, Default)]
 struct Player;
 
+// Player one is 0, counting from the left side of the court
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+struct PlayerId(usize);
+
 #[derive(Component)]
 struct Solid;
 
+// A solid the ball rebounds weakly off instead of bouncing
+#[derive(Component)]
+struct Net;
+
 #[derive(Component)]
 struct Ball;
 
@@ -19,51 +116,247 @@ struct Movement {
 }
 
 #[derive(Component, Default)]
-struct Racket;
+struct Size(Vec2);
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+enum BallState {
+    // Held in place, gravity doesn't apply
+    Held,
+    // Tossed up by the server, waiting to be hit
+    Tossed,
+    // In flight from a serve until its first bounce
+    Served,
+    InRally,
+    // Bounced too often, rolling out until put back in play
+    Dead,
+}
+
+impl BallState {
+    fn in_flight(self) -> bool {
+        matches!(self, BallState::Tossed | BallState::Served | BallState::InRally)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+enum BallType {
+    #[default]
+    Regular,
+    Giant,
+}
+
+impl BallType {
+    fn scale(self) -> f32 {
+        match self {
+            BallType::Regular => 1.0,
+            BallType::Giant => 2.0,
+        }
+    }
+}
+
+/// Spawns a ball with everything the ball systems expect on it. A ball with no
+/// velocity starts held for a serve, a moving one starts in a rally.
+fn spawn_ball(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    position: Vec2,
+    velocity: Vec2,
+    ball_type: BallType,
+) -> Entity {
+    let scale = ball_type.scale();
+    let state = if velocity == Vec2::ZERO {
+        BallState::Held
+    } else {
+        BallState::InRally
+    };
+    commands
+        .spawn((
+            Ball,
+            SpriteBundle {
+                transform: Transform {
+                    translation: position.extend(0.0),
+                    scale: Vec3::splat(2.0 * scale),
+                    ..default()
+                },
+                texture: asset_server.load("ball.png"),
+                ..default()
+            },
+            Size(Vec2::splat(BALL_SIZE * scale)),
+            Bounces::default(),
+            Spin::default(),
+            Curve::default(),
+            state,
+            Movement {
+                velocity,
+                ..default()
+            },
+        ))
+        .id()
+}
+
+// Bounces since the ball last landed on the other side of the net
+#[derive(Component, Default)]
+struct Bounces {
+    side: Option<CourtSide>,
+    count: i8,
+}
+
+impl Bounces {
+    fn record(&mut self, side: CourtSide) {
+        if self.side != Some(side) {
+            self.side = Some(side);
+            self.count = 0;
+        }
+        self.count += 1;
+    }
+}
 
+// Angular velocity of the ball in radians per second, counterclockwise positive.
+// For a ball moving right, negative is topspin and positive is slice.
 #[derive(Component, Default)]
-struct Size(Vec2);
+struct Spin(f32);
 
-#[derive(Component)]
-struct Bounces(i8);
+// Push across the ball's flight from a curve shot, as an acceleration that is set by
+// the hit and fades out. Positive y is down, like the velocity.
+#[derive(Component, Default)]
+struct Curve(Vec2);
 
-#[derive(Component)]
-struct CollidesWithPlayer;
+#[derive(Component, Default)]
+struct Dash {
+    time_left: f32,
+    cooldown: f32,
+    direction: f32,
+}
 
-#[derive(Component)]
-struct CollidesWithBall;
+#[derive(Component, Default)]
+struct Dive {
+    // Seconds left lying on the court after landing
+    knockdown: f32,
+}
 
 #[derive(Component, Default)]
 struct Jump {
     var_jump_timer: f32,
     var_jump_speed: f32,
+    // Seconds since leaving the ground that a jump is still allowed
+    coyote_timer: f32,
+    // Seconds a jump pressed in the air waits for the player to land
+    buffer_timer: f32,
+    // Jumps left before landing, refilled from the air jumps modifier on the ground
+    air_jumps_left: u32,
+}
+
+// Seconds a swing pressed before the player could start one still starts it
+#[derive(Component, Default)]
+struct SwingBuffer(f32);
+
+// What the player is doing, decided once per tick by player_movement_system.
+// Input, animation and the racket hitbox all follow from it.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default)]
+enum PlayerState {
+    #[default]
+    Idle,
+    Run,
+    Jump,
+    // A burst along the ground, no jumping until it is over
+    Dash,
+    // From the wind-up to the end of recovery, committing the player to the ground
+    Swing,
+    // Flying out sideways racket first, no control until landing
+    Dive,
+    // Down on the court after a dive, getting back up
+    Knockdown,
+}
+
+impl PlayerState {
+    fn can_jump(self) -> bool {
+        matches!(self, PlayerState::Idle | PlayerState::Run)
+    }
+
+    fn can_dash(self) -> bool {
+        matches!(self, PlayerState::Idle | PlayerState::Run)
+    }
+
+    fn can_dive(self) -> bool {
+        matches!(self, PlayerState::Idle | PlayerState::Run)
+    }
 }
 
 #[derive(Event)]
 struct SolidCollisionEvent<T: Component> {
     collider: Entity,
-    collided_x: bool,
-    collided_y: bool,
+    // The solid that stopped the move along each axis
+    collided_x: Option<Entity>,
+    collided_y: Option<Entity>,
     marker: PhantomData<T>,
 }
 
-// Process physics 60 ticks per second
-const TIME_STEP: f32 = 1.0 / 60.0;
-const VAR_JUMP_TIME: f32 = 0.2;
-const JUMP_SPEED: f32 = -105.;
-const MAX_RUN: f32 = 90.;
-const RUN_ACCEL: f32 = 1000.;
-const AIR_MULT: f32 = 0.65;
-const PLAYER_MAX_FALL_SPEED: f32 = 160.;
-const BALL_MAX_FALL_SPEED: f32 = 240.;
-const HALF_GRAV_THRESHOLD: f32 = 40.;
-const PLAYER_MASS: f32 = 900.;
-const BALL_MASS: f32 = 1500.;
-const MAX_BALL_BOUNCES: i8 = 1;
+// Declares the physics tuning constants and lists them in `TUNING`, so bug and soak
+// reports always show every value the game runs with
+macro_rules! tuning {
+    ($($name:ident: $ty:ty = $value:expr;)*) => {
+        $(const $name: $ty = $value;)*
+        const TUNING: &[(&str, &dyn std::fmt::Display)] = &[$((stringify!($name), &$name)),*];
+    };
+}
+
+tuning! {
+    // Process physics 60 ticks per second
+    TIME_STEP: f32 = 1.0 / 60.0;
+    VAR_JUMP_TIME: f32 = 0.2;
+    // Grace period to jump after running off an edge or a jittery bounce off the ground
+    COYOTE_TIME: f32 = 0.1;
+    // How early before landing a jump press still counts
+    JUMP_BUFFER_TIME: f32 = 0.1;
+    // How long a swing pressed while the racket is still busy waits to come through
+    SWING_BUFFER_TIME: f32 = 0.1;
+    JUMP_SPEED: f32 = -105.;
+    MAX_RUN: f32 = 90.;
+    DASH_SPEED: f32 = 240.;
+    DASH_TIME: f32 = 0.15;
+    // From the start of one dash to the earliest the next can start
+    DASH_COOLDOWN: f32 = 0.8;
+    DIVE_SPEED: f32 = 200.;
+    // Upward speed a dive leaves the ground with
+    DIVE_HOP: f32 = 80.;
+    KNOCKDOWN_TIME: f32 = 0.6;
+    RUN_ACCEL: f32 = 1000.;
+    AIR_MULT: f32 = 0.65;
+    PLAYER_MAX_FALL_SPEED: f32 = 160.;
+    BALL_MAX_FALL_SPEED: f32 = 240.;
+    HALF_GRAV_THRESHOLD: f32 = 40.;
+    PLAYER_MASS: f32 = 900.;
+    BALL_MASS: f32 = 1500.;
+    MAX_BALL_BOUNCES: i8 = 1;
+    // Restitution and nudge of a tight net, a slacker one scales them down
+    NET_RESTITUTION: f32 = 0.3;
+    NET_CORD_NUDGE: f32 = 30.;
+    // Horizontal speed kept after clipping the cord of a completely slack net
+    SLACK_NET_CORD_CARRY: f32 = 0.3;
+    // Acceleration per unit of spin and speed, curves a spinning ball's flight
+    MAGNUS_COEFFICIENT: f32 = 0.03;
+    // Fraction of spin lost per second in the air
+    SPIN_DECAY: f32 = 0.5;
+    // Fraction of a curve shot's push lost per second
+    CURVE_DECAY: f32 = 2.5;
+    // How much of the spinning surface's speed the court turns into forward speed
+    BOUNCE_GRIP: f32 = 0.3;
+    // Change in bounce height per radian per second of topspin
+    SPIN_BOUNCE_LIFT: f32 = 0.01;
+    // Fraction of spin left after a bounce
+    SPIN_BOUNCE_KEEP: f32 = 0.5;
+    // Deceleration of a dead ball rolling along the court
+    BALL_ROLL_FRICTION: f32 = 120.;
+}
+
 const GROUND_TILE_SIZE: f32 = 16.;
 const PLAYER_SIZE: f32 = 32.;
 const RACKET_SIZE: f32 = 16.;
 const BALL_SIZE: f32 = 16.;
+const BALL_SPAWN: Vec2 = Vec2::new(64., 0.);
+// Distance from the net each player starts at
+const PLAYER_SPAWN_X: f32 = 128.;
+// Per-player files like saved settings and tutorial progress
+const PROFILE_DIR: &str = "profile";
 
 fn approach(val: f32, target: f32, max_move: f32) -> f32 {
     if val > target {
@@ -73,33 +366,77 @@ fn approach(val: f32, target: f32, max_move: f32) -> f32 {
     }
 }
 
-fn run_velocity_x(movement: &Movement, direction: f32) -> f32 {
+fn run_velocity_x(movement: &Movement, direction: f32, max_run: f32) -> f32 {
     let mult = if movement.on_ground { 1. } else { AIR_MULT };
     approach(
         movement.velocity.x,
-        MAX_RUN * direction,
+        max_run * direction,
         RUN_ACCEL * mult * TIME_STEP,
     )
 }
 
+type PlayerMovementQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static PlayerInput,
+        &'static mut Movement,
+        &'static mut Transform,
+        &'static mut Jump,
+        &'static mut SwingBuffer,
+        &'static mut PlayerState,
+        &'static mut AnimationIndices,
+        &'static AnimationLibrary,
+        Option<&'static Racket>,
+        &'static mut Stamina,
+        &'static mut Dash,
+        &'static mut Dive,
+    ),
+    With<Player>,
+>;
+
 fn player_movement_system(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<
-        (
-            Entity,
-            &mut Movement,
-            &mut Transform,
-            &mut Jump,
-            &mut AnimationIndices,
-        ),
-        With<Player>,
-    >,
+    match_config: Res<MatchConfig>,
+    clock: Res<GameClock>,
+    infinite_stamina: Res<InfiniteStamina>,
+    mut query: PlayerMovementQuery,
     mut commands: Commands
 ) {
-    for (entity, mut movement, mut transform, mut jump, mut animation_indices) in &mut query {
-        let is_jump_key_down = keyboard_input.pressed(KeyCode::Up);
-        let is_left_key_down = keyboard_input.pressed(KeyCode::Left);
-        let is_right_key_down = keyboard_input.pressed(KeyCode::Right);
+    // The infinite stamina cheat leaves the drain out
+    let drain = |stamina: &mut Stamina, amount: f32| {
+        if !infinite_stamina.0 {
+            stamina.drain(amount);
+        }
+    };
+    for (
+        entity,
+        input,
+        mut movement,
+        mut transform,
+        mut jump,
+        mut swing_buffer,
+        mut state,
+        mut animation_indices,
+        animations,
+        racket,
+        mut stamina,
+        mut dash,
+        mut dive,
+    ) in &mut query
+    {
+        if *state == PlayerState::Dive && movement.on_ground {
+            dive.knockdown = KNOCKDOWN_TIME;
+        }
+        let is_down = dive.knockdown > 0.0;
+        if is_down {
+            dive.knockdown -= clock.delta();
+        }
+        let mut is_diving = *state == PlayerState::Dive && !movement.on_ground;
+        let in_control = !is_diving && !is_down;
+        let is_jump_key_down = input.jump && in_control;
+        let is_left_key_down = input.left && in_control;
+        let is_right_key_down = input.right && in_control;
 
         // apply gravity
         let abs_vel_y = movement.velocity.y.abs();
@@ -112,87 +449,199 @@ fn player_movement_system(
         movement.velocity.y = approach(
             movement.velocity.y,
             PLAYER_MAX_FALL_SPEED,
-            PLAYER_MASS * mult * TIME_STEP,
+            PLAYER_MASS * match_config.modifiers.gravity_scale() * mult * TIME_STEP,
         );
 
         if jump.var_jump_timer > 0.0 {
             if is_jump_key_down {
                 movement.velocity.y = jump.var_jump_speed.min(movement.velocity.y);
-                jump.var_jump_timer -= TIME_STEP;
+                jump.var_jump_timer -= clock.delta();
             } else {
                 jump.var_jump_timer = 0.0;
             }
         }
 
+        let max_run = MAX_RUN * stamina.speed_scale();
+        // Facing stays put mid-swing, so the keys can aim the return instead
+        let can_turn = racket.is_none();
         let mut is_running = false;
         if is_left_key_down {
-            movement.velocity.x = run_velocity_x(movement.as_ref(), -1.);
+            movement.velocity.x = run_velocity_x(movement.as_ref(), -1., max_run);
             is_running = true;
-            transform.rotation = Quat::from_rotation_y(std::f32::consts::PI);
+            if can_turn {
+                transform.rotation = Quat::from_rotation_y(std::f32::consts::PI);
+            }
         } else if is_right_key_down {
-            movement.velocity.x = run_velocity_x(movement.as_ref(), 1.);
+            movement.velocity.x = run_velocity_x(movement.as_ref(), 1., max_run);
             is_running = true;
-            transform.rotation = Quat::default();
-        } else {
-            movement.velocity.x = run_velocity_x(movement.as_ref(), 0.);
+            if can_turn {
+                transform.rotation = Quat::default();
+            }
+        } else if !is_diving {
+            movement.velocity.x = run_velocity_x(movement.as_ref(), 0., max_run);
+        }
+        if is_running && movement.on_ground {
+            drain(&mut stamina, RUN_DRAIN * clock.delta());
         }
 
-        if !movement.on_ground {
-            jump_animation(&mut animation_indices);
-        } else if is_running {
-            run_animation(&mut animation_indices);
-        } else {
-            idle_animation(&mut animation_indices);
+        dash.cooldown = (dash.cooldown - clock.delta()).max(0.0);
+        if input.dash_pressed && state.can_dash() && movement.on_ground && dash.cooldown <= 0.0 {
+            // Dash the way the player is heading, or the way they face when standing
+            dash.direction = if is_left_key_down {
+                -1.0
+            } else if is_right_key_down {
+                1.0
+            } else {
+                facing(&transform)
+            };
+            dash.time_left = DASH_TIME;
+            dash.cooldown = DASH_COOLDOWN;
+            drain(&mut stamina, DASH_COST);
+        }
+        let is_dashing = dash.time_left > 0.0;
+        if is_dashing {
+            movement.velocity.x = dash.direction * DASH_SPEED * stamina.speed_scale();
+            dash.time_left -= clock.delta();
         }
 
-        let is_jump_just_pressed: bool = keyboard_input.just_pressed(KeyCode::Up);
-        if is_jump_just_pressed && movement.on_ground {
+        if input.dive_pressed && state.can_dive() && movement.on_ground {
+            let direction = if is_left_key_down {
+                -1.0
+            } else if is_right_key_down {
+                1.0
+            } else {
+                facing(&transform)
+            };
+            transform.rotation = if direction < 0.0 {
+                Quat::from_rotation_y(std::f32::consts::PI)
+            } else {
+                Quat::default()
+            };
+            movement.velocity =
+                Vec2::new(direction * DIVE_SPEED * stamina.speed_scale(), -DIVE_HOP);
+            dash.time_left = 0.0;
+            commands.entity(entity).insert(Racket::diving());
+            drain(&mut stamina, DIVE_COST);
+            is_diving = true;
+        }
+
+        if movement.on_ground {
+            jump.coyote_timer = COYOTE_TIME;
+            jump.air_jumps_left = match_config.modifiers.air_jumps;
+        } else {
+            jump.coyote_timer = (jump.coyote_timer - clock.delta()).max(0.0);
+        }
+        // Dropping off the ground without jumping still counts as on it for a moment.
+        // Movement treats positive y as down, a player on the way up has jumped already.
+        let can_jump = state.can_jump()
+            || (*state == PlayerState::Jump
+                && jump.coyote_timer > 0.0
+                && movement.velocity.y >= 0.0);
+        if input.jump_pressed && in_control {
+            jump.buffer_timer = JUMP_BUFFER_TIME;
+        } else {
+            jump.buffer_timer = (jump.buffer_timer - clock.delta()).max(0.0);
+        }
+        let can_air_jump = !can_jump && *state == PlayerState::Jump && jump.air_jumps_left > 0;
+        if jump.buffer_timer > 0.0 && (can_jump || can_air_jump) {
+            if can_air_jump {
+                jump.air_jumps_left -= 1;
+                // Starts from a standstill like a jump off the ground
+                movement.velocity.y = 0.0;
+            }
             // init jump
-            movement.velocity.y -= JUMP_SPEED;
+            jump.coyote_timer = 0.0;
+            jump.buffer_timer = 0.0;
+            let jump_speed = JUMP_SPEED * stamina.speed_scale();
+            movement.velocity.y -= jump_speed;
             jump.var_jump_timer = VAR_JUMP_TIME;
-            jump.var_jump_speed = JUMP_SPEED;
+            jump.var_jump_speed = jump_speed;
+            drain(&mut stamina, JUMP_COST);
         }
 
-        let is_space_just_pressed = keyboard_input.just_pressed(KeyCode::Space);
-        if is_space_just_pressed {
-            commands.entity(entity)
-                .insert(Racket);
+        // A swing plays out once started. Pressing again mid-swing, or while the player
+        // can't swing, starts the next one as soon as they can.
+        if input.swing_pressed {
+            swing_buffer.0 = SWING_BUFFER_TIME;
+        } else {
+            swing_buffer.0 = (swing_buffer.0 - clock.delta()).max(0.0);
         }
-
-        let is_space_just_released = keyboard_input.just_released(KeyCode::Space);
-        if is_space_just_released {
-            commands.entity(entity)
-                .remove::<Racket>();
+        if swing_buffer.0 > 0.0 && racket.is_none() && in_control && !is_diving {
+            commands.entity(entity).insert(Racket::default());
+            swing_buffer.0 = 0.0;
         }
+        let next_state = if is_down {
+            PlayerState::Knockdown
+        } else if is_diving {
+            PlayerState::Dive
+        } else if input.swing_pressed || racket.is_some() {
+            PlayerState::Swing
+        } else if is_dashing {
+            PlayerState::Dash
+        } else if !movement.on_ground {
+            PlayerState::Jump
+        } else if is_running {
+            PlayerState::Run
+        } else {
+            PlayerState::Idle
+        };
+        state.set_if_neq(next_state);
+
+        *animation_indices = match *state {
+            PlayerState::Idle => animations.idle,
+            PlayerState::Run => animations.run,
+            PlayerState::Jump => animations.jump,
+            PlayerState::Dash => animations.dash,
+            PlayerState::Swing if movement.on_ground => animations.idle,
+            PlayerState::Swing => animations.jump,
+            PlayerState::Dive => animations.dive,
+            PlayerState::Knockdown => animations.knockdown,
+        };
     }
 }
 
-fn ball_movement_system(mut query: Query<&mut Movement, With<Ball>>) {
-    let mut movement = query.get_single_mut().unwrap();
-    if !movement.on_ground {
+/// Magnus acceleration on a ball moving at `velocity` with `spin`, in the same
+/// coordinates as `Movement`, positive y down.
+fn spin_acceleration(velocity: Vec2, spin: f32) -> Vec2 {
+    Vec2::new(velocity.y * spin, -velocity.x * spin) * MAGNUS_COEFFICIENT
+}
+
+fn ball_movement_system(
+    match_config: Res<MatchConfig>,
+    mut query: Query<(
+        &mut Movement,
+        &mut Transform,
+        &Size,
+        &BallState,
+        &mut Spin,
+        &mut Curve,
+    )>,
+) {
+    let (mut movement, mut transform, size, state, mut spin, mut curve) = query.single_mut();
+    if state.in_flight() {
         movement.velocity.y = approach(
             movement.velocity.y,
             BALL_MAX_FALL_SPEED,
-            BALL_MASS * TIME_STEP,
+            BALL_MASS * match_config.modifiers.gravity_scale() * TIME_STEP,
         );
+        movement.velocity.x += match_config.modifiers.wind * TIME_STEP;
+        let magnus = spin_acceleration(movement.velocity, spin.0);
+        movement.velocity += (magnus + curve.0) * TIME_STEP;
+        spin.0 *= 1.0 - SPIN_DECAY * TIME_STEP;
+        curve.0 *= 1.0 - CURVE_DECAY * TIME_STEP;
+        transform.rotate_z(spin.0 * TIME_STEP);
+    } else {
+        curve.0 = Vec2::ZERO;
+        if *state == BallState::Dead && movement.on_ground {
+            // Roll to a stop, turning as far as the ball travelled
+            movement.velocity.x =
+                approach(movement.velocity.x, 0.0, BALL_ROLL_FRICTION * TIME_STEP);
+            let radius = size.0.x / 2.0;
+            transform.rotate_z(-movement.velocity.x * TIME_STEP / radius);
+        }
     }
 }
 
-fn run_animation(animation_indices: &mut AnimationIndices) {
-    animation_indices.first = 18;
-    animation_indices.last = 21;
-}
-
-fn idle_animation(animation_indices: &mut AnimationIndices) {
-    animation_indices.first = 15;
-    animation_indices.last = 15;
-}
-
-fn jump_animation(animation_indices: &mut AnimationIndices) {
-    animation_indices.first = 17;
-    animation_indices.last = 17;
-}
-
 fn sign(number: i32) -> i32 {
     match number.cmp(&0) {
         Ordering::Less => -1,
@@ -201,87 +650,97 @@ fn sign(number: i32) -> i32 {
     }
 }
 
+type CollidingQuery<'w, 's, T> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Movement,
+        &'static mut Transform,
+        &'static Size,
+    ),
+    (With<T>, Without<Solid>),
+>;
+
 fn collision_system<T: Component>(
-    solid_query: Query<&Transform, With<Solid>>,
-    mut entity_query: Query<
-        (Entity, &mut Movement, &mut Transform, &Size),
-        (With<T>, Without<Solid>),
-    >,
+    solid_query: Query<(Entity, &Transform), With<Solid>>,
+    mut entity_query: CollidingQuery<T>,
     mut collision_events: EventWriter<SolidCollisionEvent<T>>,
 ) {
-    let (entity, mut entity_movement, mut entity_transform, entity_size) =
-        entity_query.single_mut();
-    let velocity_delta = entity_movement.velocity * TIME_STEP;
-    entity_movement.velocity_remainder += velocity_delta;
-
-    let mut move_x = entity_movement.velocity_remainder.x.round() as i32;
-    let mut collided_x = false;
-    if move_x != 0 {
-        entity_movement.velocity_remainder.x -= move_x as f32;
-        let move_sign = sign(move_x);
-
-        while move_x != 0 && !collided_x {
-            let new_kin_pos = entity_transform.translation + Vec3::new(move_sign as f32, 0.0, 0.0);
-
-            for solid_transform in &solid_query {
-                let collision = collide(
-                    solid_transform.translation,
-                    solid_transform.scale.truncate(),
-                    new_kin_pos,
-                    entity_size.0,
-                );
-
-                if collision.is_some() {
-                    collided_x = true;
-                    break;
+    for (entity, mut entity_movement, mut entity_transform, entity_size) in &mut entity_query {
+        let velocity_delta = entity_movement.velocity * TIME_STEP;
+        entity_movement.velocity_remainder += velocity_delta;
+
+        let mut move_x = entity_movement.velocity_remainder.x.round() as i32;
+        let mut collided_x = None;
+        if move_x != 0 {
+            entity_movement.velocity_remainder.x -= move_x as f32;
+            let move_sign = sign(move_x);
+
+            while move_x != 0 && collided_x.is_none() {
+                let new_kin_pos =
+                    entity_transform.translation + Vec3::new(move_sign as f32, 0.0, 0.0);
+
+                for (solid, solid_transform) in &solid_query {
+                    let collision = collide(
+                        solid_transform.translation,
+                        solid_transform.scale.truncate(),
+                        new_kin_pos,
+                        entity_size.0,
+                    );
+
+                    if collision.is_some() {
+                        collided_x = Some(solid);
+                        break;
+                    }
+                }
+                if collided_x.is_none() {
+                    entity_transform.translation.x += move_sign as f32;
+                    move_x -= move_sign;
                 }
-            }
-            if !collided_x {
-                entity_transform.translation.x += move_sign as f32;
-                move_x -= move_sign;
             }
         }
-    }
 
-    let mut move_y = entity_movement.velocity_remainder.y.round() as i32;
-    let mut collided_y = false;
-    if move_y != 0 {
-        entity_movement.velocity_remainder.y -= move_y as f32;
-        let move_sign = sign(move_y);
+        let mut move_y = entity_movement.velocity_remainder.y.round() as i32;
+        let mut collided_y = None;
+        if move_y != 0 {
+            entity_movement.velocity_remainder.y -= move_y as f32;
+            let move_sign = sign(move_y);
 
-        while move_y != 0 && !collided_y {
-            for solid_transform in &solid_query {
-                // Make it so we can use + sign here instead, right?
-                let new_kin_pos =
-                    entity_transform.translation - Vec3::new(0.0, move_sign as f32, 0.0);
-                let collision = collide(
-                    solid_transform.translation,
-                    solid_transform.scale.truncate(),
-                    new_kin_pos,
-                    entity_size.0,
-                );
-
-                if collision.is_some() {
-                    collided_y = true;
-                    break;
+            while move_y != 0 && collided_y.is_none() {
+                for (solid, solid_transform) in &solid_query {
+                    // Make it so we can use + sign here instead, right?
+                    let new_kin_pos =
+                        entity_transform.translation - Vec3::new(0.0, move_sign as f32, 0.0);
+                    let collision = collide(
+                        solid_transform.translation,
+                        solid_transform.scale.truncate(),
+                        new_kin_pos,
+                        entity_size.0,
+                    );
+
+                    if collision.is_some() {
+                        collided_y = Some(solid);
+                        break;
+                    }
+                }
+                if collided_y.is_none() {
+                    entity_transform.translation.y -= move_sign as f32;
+                    move_y -= move_sign;
                 }
             }
-            if !collided_y {
-                entity_transform.translation.y -= move_sign as f32;
-                move_y -= move_sign;
-            }
-        }
 
-        entity_movement.on_ground = collided_y;
-    }
+            entity_movement.on_ground = collided_y.is_some();
+        }
 
-    if collided_x || collided_y {
-        collision_events.send(SolidCollisionEvent::<T> {
-            collider: entity,
-            collided_x,
-            collided_y,
-            marker: default(),
-        });
+        if collided_x.is_some() || collided_y.is_some() {
+            collision_events.send(SolidCollisionEvent::<T> {
+                collider: entity,
+                collided_x,
+                collided_y,
+                marker: default(),
+            });
+        }
     }
 }
 
@@ -291,38 +750,70 @@ fn player_collision_response_system(
 ) {
     for event in events.iter() {
         let mut movement = query.get_mut(event.collider).unwrap();
-        if event.collided_x {
+        if event.collided_x.is_some() {
             movement.velocity.x = 0.0;
         }
-        if event.collided_y {
+        if event.collided_y.is_some() {
             movement.velocity.y = 0.0;
         }
     }
 }
 
 fn ball_collision_response_system(
-    mut query: Query<(&mut Movement, &mut Bounces)>,
+    court: Res<Court>,
+    net_query: Query<&Transform, With<Net>>,
+    mut query: Query<(
+        &mut Movement,
+        &mut Bounces,
+        &mut BallState,
+        &mut Spin,
+        &Transform,
+        &Size,
+    )>,
     mut events: EventReader<SolidCollisionEvent<Ball>>,
 ) {
     for event in events.iter() {
-        let (mut movement, mut bounces) = query.get_mut(event.collider).unwrap();
-        if event.collided_x {
-            movement.velocity.x *= -1.5;
+        let (mut movement, mut bounces, mut state, mut spin, transform, size) =
+            query.get_mut(event.collider).unwrap();
+        if let Some(solid) = event.collided_x {
+            let restitution = if net_query.contains(solid) {
+                NET_RESTITUTION * court.net_tension
+            } else {
+                1.5
+            };
+            movement.velocity.x *= -restitution;
         }
-        if event.collided_y {
-            if bounces.0 >= MAX_BALL_BOUNCES {
+        let net_cord = event.collided_y.and_then(|solid| net_query.get(solid).ok());
+        if let Some(net_transform) = net_cord {
+            // Clipped the net cord, drop off whichever side most of the ball is on.
+            // A slack net gives way and takes more of the pace off.
+            movement.velocity.y *= -NET_RESTITUTION * court.net_tension;
+            movement.velocity.x *=
+                SLACK_NET_CORD_CARRY + (1.0 - SLACK_NET_CORD_CARRY) * court.net_tension;
+            movement.velocity.x += (transform.translation.x - net_transform.translation.x).signum()
+                * NET_CORD_NUDGE
+                * court.net_tension;
+        } else if event.collided_y.is_some() {
+            bounces.record(court.side_of(transform.translation.x));
+            if *state == BallState::Served {
+                *state = BallState::InRally;
+            }
+            if bounces.count > MAX_BALL_BOUNCES {
                 movement.velocity.y = 0.0;
-                movement.on_ground = true;
-                bounces.0 = 0;
+                *state = BallState::Dead;
             } else {
-                movement.velocity.y *= -1.5;
-                bounces.0 += 1;
+                // The court grips the bottom of a spinning ball. Topspin kicks it on
+                // faster and higher, slice checks it and keeps it low.
+                let topspin = -spin.0 * movement.velocity.x.signum();
+                movement.velocity.x -= spin.0 * size.0.x / 2.0 * BOUNCE_GRIP;
+                movement.velocity.y *= -1.5 * (1.0 + topspin * SPIN_BOUNCE_LIFT).max(0.5);
+                spin.0 *= SPIN_BOUNCE_KEEP;
             }
         }
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy)]
 struct AnimationIndices {
     first: usize,
     last: usize,
@@ -333,7 +824,7 @@ struct AnimationIndices {
 struct AnimationTimer(Timer);
 
 fn animate_player_sprite_system(
-    time: Res<Time>,
+    clock: Res<GameClock>,
     mut query: Query<(
         &AnimationIndices,
         &mut AnimationTimer,
@@ -341,7 +832,7 @@ fn animate_player_sprite_system(
     )>,
 ) {
     for (indices, mut timer, mut sprite) in &mut query {
-        timer.tick(time.delta());
+        timer.tick(Duration::from_secs_f32(clock.delta()));
         if timer.just_finished() {
             sprite.index = if sprite.index == indices.last
                 || sprite.index < indices.first
@@ -357,24 +848,39 @@ fn animate_player_sprite_system(
 
 fn object_debug_system(
     mut gizmos: Gizmos,
+    mut hit_events: EventReader<HitConfirmed>,
     solid_query: Query<&Transform, (With<Solid>, Without<Player>)>,
-    player_query: Query<(&Transform, &Size, Option<&Racket>), With<Player>>,
+    player_query: Query<(Entity, &Transform, &Size, Option<&Racket>), With<Player>>,
     ball_query: Query<(&Transform, &Size), With<Ball>>,
 ) {
-    let (player_transform, player_size, racket) = player_query.single();
-    gizmos.rect_2d(
-        player_transform.translation.truncate(),
-        0.0,
-        player_size.0,
-        Color::GREEN,
-    );
-    if let Some(_racket) = racket {
+    let mut hitters = Vec::new();
+    for event in hit_events.iter() {
+        hitters.push(event.player);
+        // The better the contact, the bigger the mark where the ball was met
+        gizmos.circle_2d(event.position, 1.0 + 3.0 * event.quality, Color::YELLOW);
+    }
+    for (player, player_transform, player_size, racket) in &player_query {
         gizmos.rect_2d(
-            player_transform.translation.truncate() + Vec2::new(16., 0.),
+            player_transform.translation.truncate(),
             0.0,
-            Vec2::new(RACKET_SIZE, RACKET_SIZE),
-            Color::DARK_GREEN,
+            player_size.0,
+            Color::GREEN,
         );
+        if let Some(racket) = racket {
+            let color = if hitters.contains(&player) {
+                Color::YELLOW
+            } else if racket.phase() == SwingPhase::Active {
+                Color::DARK_GREEN
+            } else {
+                Color::GRAY
+            };
+            gizmos.rect_2d(
+                racket.center(player_transform, player_size),
+                0.0,
+                Vec2::splat(racket.hitbox_size()),
+                color,
+            );
+        }
     }
     let (ball_transform, ball_size) = ball_query.single();
     gizmos.rect_2d(
@@ -393,62 +899,171 @@ fn object_debug_system(
     }
 }
 
+/// Spawns a player on `side` facing the net, driven by whichever controller `slot`
+/// names. The left player is player 1.
+#[allow(clippy::too_many_arguments)]
+fn spawn_player(
+    commands: &mut Commands,
+    gamepad_assignments: &mut GamepadAssignments,
+    side: CourtSide,
+    character: &CharacterDef,
+    texture_atlas: Handle<TextureAtlas>,
+    tint: Color,
+    scale: f32,
+    slot: ControllerSlot,
+) -> Entity {
+    let id = PlayerId(side.index());
+    let (x, rotation) = match side {
+        CourtSide::Left => (-PLAYER_SPAWN_X, Quat::default()),
+        // Turned around to face the net from the far side
+        CourtSide::Right => (PLAYER_SPAWN_X, Quat::from_rotation_y(std::f32::consts::PI)),
+    };
+    let animation_indices = character.animations.run;
+    let mut player = commands.spawn((
+        SpriteSheetBundle {
+            transform: Transform {
+                translation: Vec3::new(x, 0.0, 0.0),
+                rotation,
+                scale: Vec3::splat(4.0 * scale),
+            },
+            texture_atlas,
+            sprite: TextureAtlasSprite {
+                color: tint,
+                ..TextureAtlasSprite::new(animation_indices.first)
+            },
+            ..default()
+        },
+        animation_indices,
+        character.animations,
+        AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
+        Player,
+        id,
+        Size(Vec2::splat(PLAYER_SIZE * scale)),
+        Movement { ..default() },
+        // Bundles top out at 15 components, so jump and swing buffering share a tuple
+        (Jump { ..default() }, SwingBuffer::default()),
+        Dash::default(),
+        Dive::default(),
+        PlayerState::default(),
+        PlayerInput::default(),
+        Stamina::default(),
+        side,
+    ));
+    assign_controller(&mut player, gamepad_assignments, id, slot);
+    player.id()
+}
+
+/// Hands player `id` to whichever controller `slot` names, taking it away from the
+/// one that had it.
+fn assign_controller(
+    player: &mut EntityCommands,
+    gamepad_assignments: &mut GamepadAssignments,
+    id: PlayerId,
+    slot: ControllerSlot,
+) {
+    player.remove::<(KeyboardController, AiController, AgentController)>();
+    gamepad_assignments.assign(id, None);
+    match slot {
+        ControllerSlot::Keyboard(layout) => {
+            player.insert(KeyboardController(layout));
+        }
+        ControllerSlot::Gamepad(gamepad) => {
+            gamepad_assignments.assign(id, Some(Gamepad::new(gamepad)));
+        }
+        ControllerSlot::Ai(difficulty) => {
+            player.insert(AiController { difficulty });
+        }
+        ControllerSlot::Agent(kind) => {
+            player.insert(AgentController::new(kind.create()));
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn setup_system(
     mut commands: Commands,
     query: Query<&Window, With<PrimaryWindow>>,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    characters: Res<Characters>,
+    selected_character: Res<SelectedCharacter>,
+    match_config: Res<MatchConfig>,
+    mut gamepad_assignments: ResMut<GamepadAssignments>,
 ) {
-    let Ok(window) = query.get_single() else {
-        return;
+    // A headless match has no window and plays on a court the size of the default one
+    let (width, height) = match query.get_single() {
+        Ok(window) => (window.width(), window.height()),
+        Err(_) => {
+            let resolution = WindowResolution::default();
+            (resolution.width(), resolution.height())
+        }
     };
 
     commands.spawn(Camera2dBundle::default());
     // player
-    let player_texture_handle = asset_server.load("player_atlas.png");
-    let player_texture_atlas = TextureAtlas::from_grid(
-        player_texture_handle,
-        Vec2::new(8.0, 8.0),
-        16,
-        3,
-        None,
-        None,
-    );
-    let player_texture_atlas_handle = texture_atlases.add(player_texture_atlas);
-    let animation_indices = AnimationIndices {
-        first: 18,
-        last: 21,
-    };
+    let character = selected_character.def(&characters);
+    let player_texture_atlas_handle =
+        load_character_atlas(character, &asset_server, &mut texture_atlases);
+    let player_scale = match_config.modifiers.player_scale();
+    // Both players face the net, the left one first
+    let sides = [CourtSide::Left, CourtSide::Right];
+    for (slot, side) in match_config.slots.into_iter().zip(sides) {
+        let player = spawn_player(
+            &mut commands,
+            &mut gamepad_assignments,
+            side,
+            character,
+            player_texture_atlas_handle.clone(),
+            selected_character.tint(&characters, side),
+            player_scale,
+            slot,
+        );
+        if match_config.auto_move[side.index()] {
+            commands.entity(player).insert(AutoMove);
+        }
+    }
+    // ground
+    let left_edge = -(width / 2.0);
+    let bottom_edge = -(height / 2.0);
 
     commands.spawn((
-        SpriteSheetBundle {
-            transform: Transform::from_scale(Vec3::splat(4.0)),
-            texture_atlas: player_texture_atlas_handle,
-            sprite: TextureAtlasSprite::new(animation_indices.first),
+        Solid,
+        Transform {
+            translation: Vec3::new(0.0, bottom_edge + (GROUND_TILE_SIZE / 2.0), 1.0),
+            scale: Vec3::new(width, GROUND_TILE_SIZE, 1.0),
             ..default()
         },
-        animation_indices,
-        AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-        Player,
-        Size(Vec2::new(PLAYER_SIZE, PLAYER_SIZE)),
-        Movement { ..default() },
-        Jump { ..default() },
     ));
-    // ground
-    let left_edge = (window.width() / 2.0) * -1.0;
-    let bottom_edge = (window.height() / 2.0) * -1.0;
-
+    // net
+    let net_height = match_config.modifiers.net_height();
     commands.spawn((
         Solid,
-        Transform {
-            translation: Vec3::new(0.0, bottom_edge + (GROUND_TILE_SIZE / 2.0), 1.0),
-            scale: Vec3::new(window.width(), GROUND_TILE_SIZE, 1.0),
+        Net,
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::WHITE,
+                custom_size: Some(Vec2::ONE),
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(0.0, bottom_edge + GROUND_TILE_SIZE + net_height / 2.0, 1.0),
+                scale: Vec3::new(NET_THICKNESS, net_height, 1.0),
+                ..default()
+            },
             ..default()
         },
     ));
+    commands.insert_resource(Court {
+        net_x: 0.0,
+        ground_y: bottom_edge + GROUND_TILE_SIZE,
+        net_height,
+        net_tension: match_config.net_tension,
+        service_line: SERVICE_LINE_DISTANCE,
+        baseline: BASELINE_DISTANCE,
+    });
 
     // ground tiles
-    let num_ground_tiles = (window.width() / GROUND_TILE_SIZE).ceil() as u32;
+    let num_ground_tiles = (width / GROUND_TILE_SIZE).ceil() as u32;
     let ground_tile_texture = asset_server.load("TennisCourtTile.png");
 
     for i in 0..num_ground_tiles {
@@ -467,44 +1082,128 @@ fn setup_system(
     }
 
     // ball
-    let ball_texture = asset_server.load("ball.png");
-    commands.spawn((
-        Ball,
-        SpriteBundle {
-            transform: Transform {
-                translation: Vec3::new(64.0, 0.0, 0.0),
-                scale: Vec3::splat(2.0),
-                ..default()
-            },
-            texture: ball_texture,
-            ..default()
-        },
-        Size(Vec2::new(BALL_SIZE, BALL_SIZE)),
-        Bounces(0),
-        Movement { ..default() },
-    ));
+    spawn_ball(
+        &mut commands,
+        &asset_server,
+        BALL_SPAWN,
+        Vec2::ZERO,
+        match_config.modifiers.ball_type(),
+    );
 }
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
-        .add_event::<SolidCollisionEvent<Player>>()
-        .add_event::<SolidCollisionEvent<Ball>>()
-        .add_systems(Startup, setup_system)
-        .add_systems(
-            FixedUpdate,
-            (
-                player_movement_system,
-                apply_deferred,
-                collision_system::<Player>.after(player_movement_system),
-                player_collision_response_system.after(collision_system::<Player>),
-                animate_player_sprite_system.after(player_movement_system),
-                ball_movement_system,
-                collision_system::<Ball>.after(ball_movement_system),
-                ball_collision_response_system.after(collision_system::<Ball>),
-            ),
+    let match_config = MatchConfig::from_args();
+    let mut app = App::new();
+    if match_config.headless {
+        // Everything still runs, but nothing opens a window or touches the GPU, and
+        // the loop ticks on its own at the physics rate
+        app.add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    ..default()
+                })
+                .set(RenderPlugin {
+                    wgpu_settings: WgpuSettings {
+                        backends: None,
+                        ..default()
+                    },
+                })
+                .disable::<WinitPlugin>(),
         )
-        .add_systems(PostUpdate, object_debug_system)
-        .insert_resource(FixedTime::new_from_secs(TIME_STEP))
-        .run();
+        .add_plugins(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f32(
+            TIME_STEP,
+        )));
+    } else {
+        app.add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()));
+    }
+    app.add_plugins((
+        BallTrailPlugin,
+        BugReportPlugin,
+        CatchUpPlugin,
+        CharacterPlugin,
+        CheatsPlugin,
+        ConsolePlugin,
+        ControllerPlugin,
+        DailyPlugin,
+        DatasetPlugin,
+        FocusPlugin,
+        GameClockPlugin,
+        GameStatePlugin,
+        GamepadInputPlugin,
+        HudPlugin,
+        KingOfTheCourtPlugin,
+    ))
+    // Bevy takes at most 15 plugins per tuple
+    .add_plugins((
+        LineCallsPlugin,
+        MatchSetupPlugin,
+        PausePlugin,
+        PhysicsGuardPlugin,
+        PopupPlugin,
+        ScoringPlugin,
+        ServePlugin,
+        ServeDebugPlugin,
+        SmashZonePlugin,
+        SoakPlugin,
+        StaminaPlugin,
+        TutorialPlugin,
+    ))
+    .add_event::<SolidCollisionEvent<Player>>()
+    .add_event::<SolidCollisionEvent<Ball>>()
+    .add_event::<BallHitEvent>()
+    .add_event::<HitConfirmed>()
+    .add_event::<PerfectHit>()
+    .init_resource::<Rally>()
+    .add_systems(Startup, setup_system)
+    .add_systems(Update, (perfect_hit_popup_system, escalation_tint_system))
+    .add_systems(
+        FixedUpdate,
+        (
+            advance_game_clock_system,
+            reset_player_input_system,
+            keyboard_controller_system
+                .after(reset_player_input_system)
+                .run_if(console_closed),
+            gamepad_controller_system.after(keyboard_controller_system),
+            ai_controller_system
+                .after(reset_player_input_system)
+                .run_if(system_enabled("ai")),
+            agent_controller_system.after(reset_player_input_system),
+            auto_move_system.after(gamepad_controller_system),
+            player_movement_system
+                .after(advance_game_clock_system)
+                .after(auto_move_system)
+                .after(ai_controller_system)
+                .after(agent_controller_system),
+            apply_deferred,
+            collision_system::<Player>.after(player_movement_system),
+            player_collision_response_system.after(collision_system::<Player>),
+            animate_player_sprite_system.after(player_movement_system),
+            ball_movement_system,
+            collision_system::<Ball>.after(ball_movement_system),
+            ball_collision_response_system.after(collision_system::<Ball>),
+            swing_racket_system.after(advance_game_clock_system),
+            racket_hit_system
+                .after(swing_racket_system)
+                .after(player_collision_response_system)
+                .after(ball_collision_response_system),
+            ball_hit_response_system.after(racket_hit_system),
+            confirm_hit_system.after(ball_hit_response_system),
+            reset_rally_system.after(award_point_system),
+        )
+            .in_set(GameplaySet),
+    )
+    .add_system_toggle("ai", true)
+    .add_system_toggle("collision_debug", true)
+    .add_systems(
+        PostUpdate,
+        object_debug_system.run_if(system_enabled("collision_debug")),
+    )
+    .insert_resource(FixedTime::new_from_secs(TIME_STEP))
+    .insert_resource(match_config);
+    #[cfg(feature = "metrics")]
+    app.add_plugins(metrics::MetricsPlugin);
+    app.run();
 }
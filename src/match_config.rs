@@ -0,0 +1,270 @@
+//! Settings chosen before a match starts. Modifiers are party options that override
+//! tuning when the match is set up rather than adding new mechanics.
+
+use std::env;
+
+use bevy::prelude::*;
+
+use crate::{
+    agent::AgentKind, ai::AiDifficulty, court::NET_HEIGHT, daily::DailyChallenge,
+    win_condition::WinConditionKind, BallType,
+};
+
+// Faster than any ordinary return, so only stacked speed-ups run into it
+const DEFAULT_BALL_SPEED_CAP: f32 = 900.;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum NetVariant {
+    // Speed mode, drives can be hit flat
+    Low,
+    #[default]
+    Regular,
+    // Badminton style, every shot has to be lobbed
+    High,
+}
+
+#[derive(Default)]
+pub struct MatchModifiers {
+    pub low_gravity: bool,
+    pub mirrored_controls: bool,
+    pub tiny_players: bool,
+    pub giant_ball: bool,
+    pub net: NetVariant,
+    // Long rallies raise the ball speed cap
+    pub escalation: bool,
+    // Extra jumps a player can make before landing again
+    pub air_jumps: u32,
+    // Sideways push on the ball in flight, positive toward the right
+    pub wind: f32,
+}
+
+impl MatchModifiers {
+    pub fn gravity_scale(&self) -> f32 {
+        if self.low_gravity {
+            0.5
+        } else {
+            1.0
+        }
+    }
+
+    pub fn player_scale(&self) -> f32 {
+        if self.tiny_players {
+            0.5
+        } else {
+            1.0
+        }
+    }
+
+    pub fn net_height(&self) -> f32 {
+        match self.net {
+            NetVariant::Low => NET_HEIGHT * 0.5,
+            NetVariant::Regular => NET_HEIGHT,
+            NetVariant::High => NET_HEIGHT * 3.0,
+        }
+    }
+
+    pub fn ball_type(&self) -> BallType {
+        if self.giant_ball {
+            BallType::Giant
+        } else {
+            BallType::Regular
+        }
+    }
+}
+
+/// Who controls a player slot.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ControllerSlot {
+    // Index of the key layout in the InputMap
+    Keyboard(usize),
+    Gamepad(usize),
+    Ai(AiDifficulty),
+    Agent(AgentKind),
+}
+
+impl ControllerSlot {
+    /// Parses `keyboard<n>`, `gamepad<id>`, `ai`, `ai:easy|normal|hard`, or
+    /// `agent:<name>`.
+    fn from_name(name: &str) -> Option<Self> {
+        if let Some(agent) = name.strip_prefix("agent:") {
+            return AgentKind::from_name(agent).map(ControllerSlot::Agent);
+        }
+        if let Some(difficulty) = name.strip_prefix("ai") {
+            return match difficulty.strip_prefix(':') {
+                Some(difficulty) => AiDifficulty::from_name(difficulty).map(ControllerSlot::Ai),
+                None if difficulty.is_empty() => Some(ControllerSlot::Ai(default())),
+                None => None,
+            };
+        }
+        if let Some(number) = name.strip_prefix("keyboard") {
+            let number: usize = number.parse().ok()?;
+            return number.checked_sub(1).map(ControllerSlot::Keyboard);
+        }
+        let id = name.strip_prefix("gamepad")?.parse().ok()?;
+        Some(ControllerSlot::Gamepad(id))
+    }
+}
+
+#[derive(Resource)]
+pub struct MatchConfig {
+    pub modifiers: MatchModifiers,
+    /// One slot per player, left side first.
+    pub slots: [ControllerSlot; 2],
+    /// Players past the second, waiting their turn in king of the court.
+    pub challengers: Vec<ControllerSlot>,
+    /// How taut the net is, from 0 for slack to 1 for tight. A slack net soaks up
+    /// more of the ball's speed when it is hit.
+    pub net_tension: f32,
+    /// Fastest a return can leave the racket, before any rally escalation.
+    pub ball_speed_cap: f32,
+    /// Endless AI against AI with invariant checks, see the soak module.
+    pub soak: bool,
+    /// Players, left side first, who are walked to the ball and only swing themselves.
+    pub auto_move: [bool; 2],
+    /// Off unless asked for, see the dataset module.
+    pub record_dataset: bool,
+    /// Today's challenge when playing one, it decides the rest of the setup.
+    pub daily: Option<DailyChallenge>,
+    /// What the match is played to, see the win_condition module.
+    pub win_condition: WinConditionKind,
+    /// No window and no rendering, for matches between agents or the AI.
+    pub headless: bool,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            modifiers: default(),
+            slots: [
+                ControllerSlot::Keyboard(0),
+                ControllerSlot::Ai(AiDifficulty::Normal),
+            ],
+            challengers: Vec::new(),
+            net_tension: 1.0,
+            ball_speed_cap: DEFAULT_BALL_SPEED_CAP,
+            soak: false,
+            auto_move: [false; 2],
+            record_dataset: false,
+            daily: None,
+            win_condition: default(),
+            headless: false,
+        }
+    }
+}
+
+impl MatchConfig {
+    /// Reads modifiers and player slots from command line flags, e.g.
+    /// `--giant-ball --p2=ai:hard`, until there is a versus setup screen to pick them
+    /// from. `--two-players` is short for `--p2=keyboard2`, and `--soak` puts the AI on
+    /// both sides unless a later `--p1` or `--p2` says otherwise. `--auto-move=p1|p2`
+    /// turns on the one-button assist for that player. `--first-to=<points>` and
+    /// `--timed=<seconds>` play to something other than a best of three sets.
+    /// `--king-of-the-court` rotates in the players given with `--p3=`, `--p4=` and so
+    /// on, or a third keyboard player if there are none. `--headless` plays without a
+    /// window, see `agent` for putting bots on the court.
+    pub fn from_args() -> Self {
+        let mut config = MatchConfig::default();
+        for arg in env::args().skip(1) {
+            match arg.as_str() {
+                "--low-gravity" => config.modifiers.low_gravity = true,
+                "--mirrored-controls" => config.modifiers.mirrored_controls = true,
+                "--tiny-players" => config.modifiers.tiny_players = true,
+                "--giant-ball" => config.modifiers.giant_ball = true,
+                "--low-net" => config.modifiers.net = NetVariant::Low,
+                "--high-net" => config.modifiers.net = NetVariant::High,
+                "--escalation" => config.modifiers.escalation = true,
+                "--two-players" => config.slots[1] = ControllerSlot::Keyboard(1),
+                "--soak" => {
+                    config.soak = true;
+                    config.slots = [ControllerSlot::Ai(default()), ControllerSlot::Ai(default())];
+                }
+                "--auto-move=p1" => config.auto_move[0] = true,
+                "--auto-move=p2" => config.auto_move[1] = true,
+                "--record-dataset" => config.record_dataset = true,
+                "--headless" => config.headless = true,
+                "--daily" => config.daily = Some(DailyChallenge::today()),
+                "--king-of-the-court" => config.win_condition = WinConditionKind::KingOfTheCourt,
+                _ if arg.starts_with("--net-tension=") => {
+                    match arg["--net-tension=".len()..].parse::<f32>() {
+                        Ok(tension) => config.net_tension = tension.clamp(0.0, 1.0),
+                        Err(_) => warn!("Net tension '{arg}' is not a number"),
+                    }
+                }
+                _ if arg.starts_with("--air-jumps=") => {
+                    match arg["--air-jumps=".len()..].parse::<u32>() {
+                        Ok(jumps) => config.modifiers.air_jumps = jumps,
+                        Err(_) => warn!("Air jumps '{arg}' is not a whole number"),
+                    }
+                }
+                _ if arg.starts_with("--wind=") => match arg["--wind=".len()..].parse::<f32>() {
+                    Ok(wind) => config.modifiers.wind = wind,
+                    Err(_) => warn!("Wind '{arg}' is not a number"),
+                },
+                _ if arg.starts_with("--first-to=") => {
+                    match arg["--first-to=".len()..].parse::<u32>() {
+                        Ok(points) if points > 0 => {
+                            config.win_condition = WinConditionKind::FirstTo(points)
+                        }
+                        _ => warn!("First to '{arg}' is not a positive whole number"),
+                    }
+                }
+                _ if arg.starts_with("--timed=") => match arg["--timed=".len()..].parse::<f32>() {
+                    Ok(seconds) if seconds > 0.0 => {
+                        config.win_condition = WinConditionKind::Timed(seconds)
+                    }
+                    _ => warn!("Match length '{arg}' is not a positive number"),
+                },
+                _ if arg.starts_with("--ball-speed-cap=") => {
+                    match arg["--ball-speed-cap=".len()..].parse::<f32>() {
+                        Ok(cap) if cap > 0.0 => config.ball_speed_cap = cap,
+                        _ => warn!("Ball speed cap '{arg}' is not a positive number"),
+                    }
+                }
+                _ => {
+                    // --p<n>=<controller>, numbered from 1
+                    let slot_arg = arg
+                        .strip_prefix("--p")
+                        .and_then(|arg| arg.split_once('='))
+                        .and_then(|(number, name)| Some((number.parse::<usize>().ok()?, name)))
+                        .filter(|(number, _)| *number > 0);
+                    let Some((number, name)) = slot_arg else {
+                        continue;
+                    };
+                    let Some(slot) = ControllerSlot::from_name(name) else {
+                        warn!("Unknown controller '{name}' for player {number}");
+                        continue;
+                    };
+                    if number <= 2 {
+                        config.slots[number - 1] = slot;
+                    } else {
+                        // Players skipped over in between are left to the AI
+                        let index = number - 3;
+                        if config.challengers.len() <= index {
+                            config
+                                .challengers
+                                .resize(index + 1, ControllerSlot::Ai(default()));
+                        }
+                        config.challengers[index] = slot;
+                    }
+                }
+            }
+        }
+        if let Some(challenge) = config.daily.clone() {
+            challenge.apply(&mut config);
+        }
+        if config.win_condition == WinConditionKind::KingOfTheCourt && config.challengers.is_empty()
+        {
+            config.challengers.push(ControllerSlot::Keyboard(2));
+        }
+        config
+    }
+
+    /// Nobody is at the keyboard, the match plays itself.
+    pub fn unattended(&self) -> bool {
+        self.soak
+            || self
+                .slots
+                .iter()
+                .all(|slot| matches!(slot, ControllerSlot::Ai(_) | ControllerSlot::Agent(_)))
+    }
+}
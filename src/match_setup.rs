@@ -0,0 +1,220 @@
+//! The players screen, opened from the pause menu, where each player slot is handed to
+//! a key layout, a connected gamepad or the AI at a difficulty. Up and Down pick a
+//! slot, Left and Right change who controls it, and Enter on Start writes the slots
+//! to the `MatchConfig` and restarts the match with them. Escape goes back to the
+//! pause menu without changing anything. Players past the second only show up when
+//! king of the court was started with them.
+
+use bevy::prelude::*;
+
+use crate::{
+    ai::AiDifficulty,
+    assign_controller,
+    controller::InputMap,
+    game_state::GameState,
+    gamepad::GamepadAssignments,
+    match_config::{ControllerSlot, MatchConfig},
+    scoring::RestartMatch,
+    PlayerId,
+};
+
+const MENU_FONT_SIZE: f32 = 24.0;
+const ROW_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const SELECTED_COLOR: Color = Color::rgb(0.35, 0.35, 0.35);
+
+// The slots being edited, copied from the match config when the screen opens
+#[derive(Resource, Default)]
+struct MatchSetup {
+    slots: Vec<ControllerSlot>,
+    // Index into `slots`, or one past the last slot for Start
+    selected: usize,
+}
+
+#[derive(Component)]
+struct MatchSetupRoot;
+
+// One per slot, then Start
+#[derive(Component)]
+struct SetupRow(usize);
+
+fn slot_name(slot: ControllerSlot) -> String {
+    match slot {
+        ControllerSlot::Keyboard(layout) => format!("Keyboard {}", layout + 1),
+        ControllerSlot::Gamepad(id) => format!("Gamepad {id}"),
+        ControllerSlot::Ai(difficulty) => format!("AI {difficulty:?}"),
+        ControllerSlot::Agent(kind) => format!("Agent {kind:?}"),
+    }
+}
+
+// Everything a slot can be handed to right now, in the order Left and Right step
+// through them
+fn slot_choices(input_map: &InputMap, gamepads: &Gamepads) -> Vec<ControllerSlot> {
+    let keyboards = (0..input_map.layout_count()).map(ControllerSlot::Keyboard);
+    let pads = gamepads
+        .iter()
+        .map(|gamepad| ControllerSlot::Gamepad(gamepad.id));
+    let ai = [AiDifficulty::Easy, AiDifficulty::Normal, AiDifficulty::Hard]
+        .into_iter()
+        .map(ControllerSlot::Ai);
+    keyboards.chain(pads).chain(ai).collect()
+}
+
+fn open_match_setup_system(
+    mut commands: Commands,
+    match_config: Res<MatchConfig>,
+    mut setup: ResMut<MatchSetup>,
+) {
+    setup.slots = match_config
+        .slots
+        .iter()
+        .chain(&match_config.challengers)
+        .copied()
+        .collect();
+    setup.selected = 0;
+
+    let text_style = TextStyle {
+        font_size: MENU_FONT_SIZE,
+        color: Color::WHITE,
+        ..default()
+    };
+    commands
+        .spawn((
+            MatchSetupRoot,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                // Over the HUD
+                z_index: ZIndex::Global(1),
+                ..default()
+            },
+        ))
+        .with_children(|menu| {
+            menu.spawn(
+                TextBundle::from_section("Players", text_style.clone()).with_style(Style {
+                    margin: UiRect::bottom(Val::Px(16.0)),
+                    ..default()
+                }),
+            );
+            for row in 0..=setup.slots.len() {
+                menu.spawn((
+                    SetupRow(row),
+                    TextBundle::from_section("", text_style.clone())
+                        .with_background_color(ROW_COLOR)
+                        .with_style(Style {
+                            width: Val::Px(320.0),
+                            margin: UiRect::all(Val::Px(4.0)),
+                            padding: UiRect::all(Val::Px(6.0)),
+                            ..default()
+                        }),
+                ));
+            }
+        });
+}
+
+fn close_match_setup_system(mut commands: Commands, query: Query<Entity, With<MatchSetupRoot>>) {
+    for root in &query {
+        commands.entity(root).despawn_recursive();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn match_setup_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    gamepads: Res<Gamepads>,
+    mut setup: ResMut<MatchSetup>,
+    mut match_config: ResMut<MatchConfig>,
+    mut gamepad_assignments: ResMut<GamepadAssignments>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut restart_events: EventWriter<RestartMatch>,
+    player_query: Query<(Entity, &PlayerId)>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Paused);
+        return;
+    }
+    let rows = setup.slots.len() + 1;
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        setup.selected = (setup.selected + rows - 1) % rows;
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        setup.selected = (setup.selected + 1) % rows;
+    }
+
+    let step = keyboard_input.just_pressed(KeyCode::Right) as isize
+        - keyboard_input.just_pressed(KeyCode::Left) as isize;
+    let selected = setup.selected;
+    if step != 0 && selected < setup.slots.len() {
+        let choices = slot_choices(&input_map, &gamepads);
+        // A slot no longer on offer, like an unplugged pad, steps onto the first choice
+        let next = match choices
+            .iter()
+            .position(|slot| *slot == setup.slots[selected])
+        {
+            Some(current) => (current as isize + step).rem_euclid(choices.len() as isize),
+            None => 0,
+        };
+        setup.slots[selected] = choices[next as usize];
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Return) && selected == setup.slots.len() {
+        match_config.slots = [setup.slots[0], setup.slots[1]];
+        match_config.challengers = setup.slots[2..].to_vec();
+        for (entity, id) in &player_query {
+            assign_controller(
+                &mut commands.entity(entity),
+                &mut gamepad_assignments,
+                *id,
+                setup.slots[id.0],
+            );
+        }
+        restart_events.send(RestartMatch);
+        next_state.set(GameState::Serving);
+    }
+}
+
+fn update_match_setup_system(
+    setup: Res<MatchSetup>,
+    mut query: Query<(&SetupRow, &mut Text, &mut BackgroundColor)>,
+) {
+    if !setup.is_changed() {
+        return;
+    }
+    for (row, mut text, mut color) in &mut query {
+        text.sections[0].value = match setup.slots.get(row.0) {
+            Some(slot) => format!("P{}: < {} >", row.0 + 1, slot_name(*slot)),
+            None => "Start".to_string(),
+        };
+        *color = if row.0 == setup.selected {
+            SELECTED_COLOR
+        } else {
+            ROW_COLOR
+        }
+        .into();
+    }
+}
+
+pub struct MatchSetupPlugin;
+
+impl Plugin for MatchSetupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MatchSetup>()
+            .add_systems(OnEnter(GameState::Menu), open_match_setup_system)
+            .add_systems(OnExit(GameState::Menu), close_match_setup_system)
+            .add_systems(
+                Update,
+                (match_setup_system, update_match_setup_system)
+                    .chain()
+                    .run_if(in_state(GameState::Menu)),
+            );
+    }
+}
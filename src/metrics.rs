@@ -0,0 +1,102 @@
+//! Gameplay counters for profiling long runs, like AI against AI soak tests. Only
+//! built with the `metrics` feature. Every few seconds a row is appended to
+//! `metrics.csv`, so leaks and slowdowns show up as trends over the run.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use bevy::{ecs::entity::Entities, prelude::*};
+
+use crate::{
+    catch_up::{within_catch_up_limit, CatchUp, CatchUpSet},
+    Ball, Player, SolidCollisionEvent,
+};
+
+const METRICS_FILE: &str = "metrics.csv";
+const METRICS_INTERVAL: f32 = 5.0;
+
+#[derive(Resource)]
+struct Metrics {
+    // None if the file couldn't be written, the counters still run
+    file: Option<BufWriter<File>>,
+    timer: Timer,
+    ticks: u64,
+    collisions: u64,
+}
+
+impl Metrics {
+    fn create() -> Self {
+        let file = File::create(METRICS_FILE)
+            .map(BufWriter::new)
+            .and_then(|mut file| {
+                writeln!(file, "seconds,ticks,dropped_ticks,collisions,entities")?;
+                Ok(file)
+            });
+        if let Err(error) = &file {
+            warn!("Failed to create {METRICS_FILE}: {error}");
+        }
+        Self {
+            file: file.ok(),
+            timer: Timer::from_seconds(METRICS_INTERVAL, TimerMode::Repeating),
+            ticks: 0,
+            collisions: 0,
+        }
+    }
+}
+
+fn count_tick_system(mut metrics: ResMut<Metrics>) {
+    metrics.ticks += 1;
+}
+
+fn count_collisions_system(
+    mut metrics: ResMut<Metrics>,
+    mut player_events: EventReader<SolidCollisionEvent<Player>>,
+    mut ball_events: EventReader<SolidCollisionEvent<Ball>>,
+) {
+    metrics.collisions += (player_events.len() + ball_events.len()) as u64;
+    player_events.clear();
+    ball_events.clear();
+}
+
+fn export_metrics_system(
+    time: Res<Time>,
+    catch_up: Res<CatchUp>,
+    entities: &Entities,
+    mut metrics: ResMut<Metrics>,
+) {
+    if !metrics.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let row = format!(
+        "{:.1},{},{},{},{}",
+        time.elapsed_seconds(),
+        metrics.ticks,
+        catch_up.dropped_total(),
+        metrics.collisions,
+        entities.len()
+    );
+    let Some(file) = metrics.file.as_mut() else {
+        return;
+    };
+    if let Err(error) = writeln!(file, "{row}").and_then(|_| file.flush()) {
+        warn!("Failed to write {METRICS_FILE}, metrics export stopped: {error}");
+        metrics.file = None;
+    }
+}
+
+pub struct MetricsPlugin;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Metrics::create())
+            .add_systems(
+                FixedUpdate,
+                count_tick_system
+                    .after(CatchUpSet)
+                    .run_if(within_catch_up_limit),
+            )
+            .add_systems(Last, (count_collisions_system, export_metrics_system));
+    }
+}
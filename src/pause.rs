@@ -0,0 +1,246 @@
+//! Escape pauses a match and shows the pause menu over it. The physics tick stops
+//! while the game is in `GameState::Paused`, and Escape again or Resume goes back
+//! to the state the match was paused in. The menu is picked from with the arrow keys
+//! and Enter, or with the mouse.
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    game_clock::GameClock, game_state::GameState, hud::HudSettings, scoring::RestartMatch,
+};
+
+const MENU_FONT_SIZE: f32 = 24.0;
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const SELECTED_COLOR: Color = Color::rgb(0.35, 0.35, 0.35);
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum PauseOption {
+    Resume,
+    Restart,
+    // Who controls each player, see the match_setup module
+    Players,
+    // The HUD preset, the one setting worth changing mid match
+    Settings,
+    Quit,
+}
+
+impl PauseOption {
+    const ALL: [PauseOption; 5] = [
+        PauseOption::Resume,
+        PauseOption::Restart,
+        PauseOption::Players,
+        PauseOption::Settings,
+        PauseOption::Quit,
+    ];
+
+    fn label(self, hud: &HudSettings) -> String {
+        match self {
+            PauseOption::Resume => "Resume".to_string(),
+            PauseOption::Restart => "Restart Match".to_string(),
+            PauseOption::Players => "Players".to_string(),
+            PauseOption::Settings => format!("Settings: HUD {}", hud.preset_name()),
+            PauseOption::Quit => "Quit".to_string(),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct PauseMenu {
+    // The state to go back to on resume
+    paused_from: GameState,
+    selected: usize,
+}
+
+impl Default for PauseMenu {
+    fn default() -> Self {
+        Self {
+            paused_from: GameState::Serving,
+            selected: 0,
+        }
+    }
+}
+
+#[derive(Component)]
+struct PauseMenuRoot;
+
+fn setup_pause_menu_system(mut commands: Commands, hud: Res<HudSettings>) {
+    let text_style = TextStyle {
+        font_size: MENU_FONT_SIZE,
+        color: Color::WHITE,
+        ..default()
+    };
+    commands
+        .spawn((
+            PauseMenuRoot,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                visibility: Visibility::Hidden,
+                // Over the HUD
+                z_index: ZIndex::Global(1),
+                ..default()
+            },
+        ))
+        .with_children(|menu| {
+            menu.spawn(
+                TextBundle::from_section("Paused", text_style.clone()).with_style(Style {
+                    margin: UiRect::bottom(Val::Px(16.0)),
+                    ..default()
+                }),
+            );
+            for option in PauseOption::ALL {
+                menu.spawn((
+                    option,
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(280.0),
+                            margin: UiRect::all(Val::Px(4.0)),
+                            padding: UiRect::all(Val::Px(6.0)),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        background_color: BUTTON_COLOR.into(),
+                        ..default()
+                    },
+                ))
+                .with_children(|button| {
+                    button.spawn(TextBundle::from_section(
+                        option.label(&hud),
+                        text_style.clone(),
+                    ));
+                });
+            }
+        });
+}
+
+fn toggle_pause_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut menu: ResMut<PauseMenu>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    match *state.get() {
+        GameState::Paused => next_state.set(menu.paused_from),
+        playing if playing.is_gameplay() => {
+            menu.paused_from = playing;
+            menu.selected = 0;
+            next_state.set(GameState::Paused);
+        }
+        _ => {}
+    }
+}
+
+fn show_pause_menu_system(
+    mut clock: ResMut<GameClock>,
+    mut query: Query<&mut Visibility, With<PauseMenuRoot>>,
+) {
+    clock.paused = true;
+    *query.single_mut() = Visibility::Inherited;
+}
+
+fn hide_pause_menu_system(
+    mut clock: ResMut<GameClock>,
+    mut query: Query<&mut Visibility, With<PauseMenuRoot>>,
+) {
+    clock.paused = false;
+    *query.single_mut() = Visibility::Hidden;
+}
+
+fn pause_menu_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut menu: ResMut<PauseMenu>,
+    mut hud: ResMut<HudSettings>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut restart_events: EventWriter<RestartMatch>,
+    mut exit_events: EventWriter<AppExit>,
+    interaction_query: Query<(&PauseOption, &Interaction), Changed<Interaction>>,
+) {
+    let count = PauseOption::ALL.len();
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        menu.selected = (menu.selected + count - 1) % count;
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        menu.selected = (menu.selected + 1) % count;
+    }
+    let mut chosen = keyboard_input
+        .just_pressed(KeyCode::Return)
+        .then_some(PauseOption::ALL[menu.selected]);
+    for (option, interaction) in &interaction_query {
+        match interaction {
+            Interaction::Pressed => chosen = Some(*option),
+            // ALL is in declaration order
+            Interaction::Hovered => menu.selected = *option as usize,
+            Interaction::None => {}
+        }
+    }
+
+    match chosen {
+        Some(PauseOption::Resume) => next_state.set(menu.paused_from),
+        Some(PauseOption::Restart) => {
+            restart_events.send(RestartMatch);
+            next_state.set(GameState::Serving);
+        }
+        Some(PauseOption::Players) => next_state.set(GameState::Menu),
+        Some(PauseOption::Settings) => {
+            hud.cycle_preset();
+            if let Err(error) = hud.save() {
+                warn!("Failed to save the HUD settings: {error}");
+            }
+        }
+        Some(PauseOption::Quit) => exit_events.send(AppExit),
+        None => {}
+    }
+}
+
+fn update_pause_menu_system(
+    menu: Res<PauseMenu>,
+    hud: Res<HudSettings>,
+    mut button_query: Query<(&PauseOption, &Children, &mut BackgroundColor)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !menu.is_changed() && !hud.is_changed() {
+        return;
+    }
+    for (option, children, mut color) in &mut button_query {
+        *color = if PauseOption::ALL[menu.selected] == *option {
+            SELECTED_COLOR
+        } else {
+            BUTTON_COLOR
+        }
+        .into();
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            text.sections[0].value = option.label(&hud);
+        }
+    }
+}
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseMenu>()
+            .add_systems(Startup, setup_pause_menu_system)
+            .add_systems(OnEnter(GameState::Paused), show_pause_menu_system)
+            .add_systems(OnExit(GameState::Paused), hide_pause_menu_system)
+            .add_systems(
+                Update,
+                (
+                    toggle_pause_system,
+                    (pause_menu_system, update_pause_menu_system)
+                        .chain()
+                        .run_if(in_state(GameState::Paused)),
+                ),
+            );
+    }
+}
@@ -0,0 +1,124 @@
+//! Catches the physics going bad before it spreads. After each physics stage every
+//! moving entity is checked for NaN or infinite values and for absurd speeds and
+//! positions. Debug builds stop right there. Release builds log a warning, clamp a
+//! speed that is only too high, and put anything broken back where it spawned.
+
+use bevy::prelude::*;
+
+use crate::{
+    ball_collision_response_system, ball_movement_system, collision_system,
+    game_state::GameplaySet,
+    match_config::MatchConfig,
+    player_collision_response_system,
+    racket::{ball_hit_response_system, racket_hit_system},
+    serve::ServeState,
+    Ball, BallState, Bounces, Movement, PlayerId, Spin, BALL_SPAWN, PLAYER_SPAWN_X,
+};
+
+// Several times anything a shot or a bounce should reach
+const MAX_SPEED: f32 = 5000.;
+// Far outside any window the game opens
+const MAX_COORDINATE: f32 = 10_000.;
+
+/// Sent whenever a guard finds something wrong, with what it found.
+#[derive(Event)]
+pub struct GuardTripped {
+    pub problem: String,
+}
+
+type GuardQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Transform,
+        &'static mut Movement,
+        Option<&'static PlayerId>,
+        Option<(
+            &'static mut BallState,
+            &'static mut Bounces,
+            &'static mut Spin,
+        )>,
+    ),
+>;
+
+// A guard system that names `stage` in its warnings
+fn physics_guard(
+    stage: &'static str,
+) -> impl FnMut(Res<MatchConfig>, ResMut<ServeState>, GuardQuery, EventWriter<GuardTripped>) {
+    move |match_config: Res<MatchConfig>,
+          mut serve_state: ResMut<ServeState>,
+          mut query: GuardQuery,
+          mut tripped_events: EventWriter<GuardTripped>| {
+        for (entity, mut transform, mut movement, player, ball) in &mut query {
+            let position = transform.translation.truncate();
+            let broken = !position.is_finite()
+                || !movement.velocity.is_finite()
+                || !movement.velocity_remainder.is_finite()
+                || position.abs().max_element() > MAX_COORDINATE;
+            let too_fast = movement.velocity.length() > MAX_SPEED;
+            if !broken && !too_fast {
+                continue;
+            }
+
+            let problem = format!(
+                "{entity:?} after {stage} at {position} moving {}",
+                movement.velocity
+            );
+            // Soak runs are there to collect these, so they keep going
+            if cfg!(debug_assertions) && !match_config.soak {
+                panic!("Invalid physics state for {problem}");
+            }
+            if !broken {
+                warn!("Clamping the speed of {problem}");
+                movement.velocity = movement.velocity.clamp_length_max(MAX_SPEED);
+                tripped_events.send(GuardTripped { problem });
+                continue;
+            }
+
+            warn!("Resetting {problem}");
+            movement.velocity = Vec2::ZERO;
+            movement.velocity_remainder = Vec2::ZERO;
+            transform.rotation = Quat::IDENTITY;
+            if let Some((mut state, mut bounces, mut spin)) = ball {
+                // The point is replayed from a fresh serve
+                transform.translation = BALL_SPAWN.extend(0.0);
+                *state = BallState::Held;
+                *bounces = Bounces::default();
+                spin.0 = 0.0;
+                *serve_state = ServeState::Ready;
+            } else if let Some(player) = player {
+                let x = if player.0 == 0 {
+                    -PLAYER_SPAWN_X
+                } else {
+                    PLAYER_SPAWN_X
+                };
+                transform.translation = Vec3::new(x, 0.0, 0.0);
+            }
+            tripped_events.send(GuardTripped { problem });
+        }
+    }
+}
+
+pub struct PhysicsGuardPlugin;
+
+impl Plugin for PhysicsGuardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GuardTripped>().add_systems(
+            FixedUpdate,
+            (
+                physics_guard("ball movement")
+                    .after(ball_movement_system)
+                    .before(collision_system::<Ball>),
+                physics_guard("collisions")
+                    .after(player_collision_response_system)
+                    .after(ball_collision_response_system)
+                    .before(racket_hit_system),
+                physics_guard("racket hit")
+                    .after(racket_hit_system)
+                    .before(ball_hit_response_system),
+            )
+                .in_set(GameplaySet),
+        );
+    }
+}
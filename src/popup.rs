@@ -0,0 +1,85 @@
+//! Floating text, like "OUT!" where a ball landed or "Perfect!" over a well timed
+//! serve. Any system can put one up by sending `SpawnPopup`. Popups rise and fade out,
+//! then stay hidden in a pool for the next one to reuse.
+
+use bevy::prelude::*;
+
+const POPUP_TIME: f32 = 1.0;
+const POPUP_RISE: f32 = 24.0;
+const POPUP_FONT_SIZE: f32 = 24.0;
+
+/// Shows `text` just above `position` in world space.
+#[derive(Event)]
+pub struct SpawnPopup {
+    pub text: String,
+    pub position: Vec2,
+    pub color: Color,
+}
+
+// Seconds left before the popup disappears, None while it is free for reuse
+#[derive(Component, Default)]
+struct Popup(Option<f32>);
+
+fn spawn_popup_system(
+    mut commands: Commands,
+    mut popup_events: EventReader<SpawnPopup>,
+    mut query: Query<(&mut Popup, &mut Text, &mut Transform, &mut Visibility)>,
+) {
+    for event in popup_events.iter() {
+        let section = TextSection::new(
+            event.text.clone(),
+            TextStyle {
+                font_size: POPUP_FONT_SIZE,
+                color: event.color,
+                ..default()
+            },
+        );
+        let translation = (event.position + Vec2::new(0.0, POPUP_RISE)).extend(10.0);
+
+        let free = query.iter_mut().find(|(popup, ..)| popup.0.is_none());
+        if let Some((mut popup, mut text, mut transform, mut visibility)) = free {
+            popup.0 = Some(POPUP_TIME);
+            text.sections = vec![section];
+            transform.translation = translation;
+            *visibility = Visibility::Visible;
+        } else {
+            commands.spawn((
+                Popup(Some(POPUP_TIME)),
+                Text2dBundle {
+                    text: Text::from_sections([section]),
+                    transform: Transform::from_translation(translation),
+                    ..default()
+                },
+            ));
+        }
+    }
+}
+
+fn float_popup_system(
+    time: Res<Time>,
+    mut query: Query<(&mut Popup, &mut Transform, &mut Text, &mut Visibility)>,
+) {
+    for (mut popup, mut transform, mut text, mut visibility) in &mut query {
+        let Some(remaining) = popup.0.as_mut() else {
+            continue;
+        };
+        *remaining -= time.delta_seconds();
+        if *remaining <= 0.0 {
+            popup.0 = None;
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        let progress = *remaining / POPUP_TIME;
+        transform.translation.y += POPUP_RISE * time.delta_seconds() / POPUP_TIME;
+        text.sections[0].style.color.set_a(progress);
+    }
+}
+
+pub struct PopupPlugin;
+
+impl Plugin for PopupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnPopup>()
+            .add_systems(Update, (spawn_popup_system, float_popup_system).chain());
+    }
+}
@@ -0,0 +1,456 @@
+use bevy::{prelude::*, sprite::collide_aabb::collide};
+
+use crate::{
+    controller::PlayerInput,
+    game_clock::GameClock,
+    match_config::{MatchConfig, MatchModifiers},
+    popup::SpawnPopup,
+    scoring::PointWon,
+    Ball, BallState, Bounces, Curve, Movement, Player, Size, Spin, RACKET_SIZE, TIME_STEP,
+};
+
+// Launch velocity of a return hit at the bottom of the swing, positive y is up
+const RETURN_VELOCITY: Vec2 = Vec2::new(320., 420.);
+// High and slow, over the head of a player at the net
+const LOB_VELOCITY: Vec2 = Vec2::new(200., 620.);
+// Soft, just over the net
+const DROP_VELOCITY: Vec2 = Vec2::new(140., 260.);
+// Hit down hard from above the ball
+const SMASH_VELOCITY: Vec2 = Vec2::new(480., -160.);
+// How much of the player's running speed carries over into the return
+const RUN_CARRY: f32 = 0.5;
+// The racket sweeps from this angle above the shoulder line down to the same angle
+// below it, in radians
+const SWING_ARC: f32 = 1.0;
+// Racket drawn back before it comes through, the hitbox isn't live yet
+const WIND_UP_TIME: f32 = 0.08;
+// Time to sweep the arc, the only part of the swing that can hit the ball
+const SWING_TIME: f32 = 0.2;
+// Bringing the racket back after the arc, no new swing until it is done
+const RECOVERY_TIME: f32 = 0.15;
+// How much the launch angle flattens per radian the contact is above the bottom of the
+// swing. Early contact drives the ball low and long, late contact lifts it.
+const CONTACT_LOFT: f32 = 0.35;
+// Fraction of the racket's speed across the back of the ball that becomes spin
+const SPIN_TRANSFER: f32 = 0.5;
+// Balls met this close to the middle of the racket get the full sweet spot
+const SWEET_SPOT_RADIUS: f32 = 4.0;
+// Fraction of full power left for a hit off the edge of the racket
+const MIN_HIT_POWER: f32 = 0.6;
+// How much of the hit quality is lost by meeting the ball at the end of the swing
+const LATE_CONTACT_PENALTY: f32 = 0.5;
+// Launch angle error for a ball hit off the top or bottom edge, in radians
+const MISHIT_ANGLE: f32 = 0.3;
+// Launch angle change from aiming a return deep or short, in radians
+const AIM_ANGLE: f32 = 0.15;
+// Speed change from aiming a return deep or short, as a fraction of the shot's speed
+const AIM_POWER: f32 = 0.15;
+// With the escalation modifier, every few returns in a rally raise the speed cap by
+// a step, up to a limit
+const RETURNS_PER_ESCALATION: u32 = 4;
+const ESCALATION_STEP: f32 = 0.1;
+const MAX_ESCALATION: u32 = 5;
+// A sweet spot hit this soon after starting the swing is perfect
+const PERFECT_WINDOW: f32 = 2.0 * TIME_STEP;
+// How much further out, and how much bigger, the racket is at full stretch in a dive
+const DIVE_REACH: f32 = 1.75;
+// Strongest push a curve shot puts across the ball's flight
+const MAX_CURVE: f32 = 500.;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SwingPhase {
+    WindUp,
+    Active,
+    Recovery,
+}
+
+/// Out from the press of the swing button until the swing has recovered. The racket
+/// winds up, travels along an arc in front of the player and is brought back.
+#[derive(Component)]
+pub struct Racket {
+    // Seconds since the swing started, wind-up included
+    swing_time: f32,
+    // Scale on the racket's distance from the player and on its hitbox
+    reach: f32,
+    // Most curve the racket can put on a shot, as an acceleration
+    max_curve: f32,
+}
+
+impl Default for Racket {
+    fn default() -> Self {
+        Self {
+            swing_time: 0.0,
+            reach: 1.0,
+            max_curve: MAX_CURVE,
+        }
+    }
+}
+
+impl Racket {
+    /// The racket thrown out at arm's length in a dive. There is no wind-up, it can
+    /// hit the ball right away, but there is no wrist left to curve it with.
+    pub fn diving() -> Self {
+        Self {
+            swing_time: WIND_UP_TIME,
+            reach: DIVE_REACH,
+            max_curve: 0.0,
+        }
+    }
+
+    /// Side length of the square that hits the ball.
+    pub fn hitbox_size(&self) -> f32 {
+        RACKET_SIZE * self.reach
+    }
+
+    pub fn phase(&self) -> SwingPhase {
+        if self.swing_time < WIND_UP_TIME {
+            SwingPhase::WindUp
+        } else if self.swing_time < WIND_UP_TIME + SWING_TIME {
+            SwingPhase::Active
+        } else {
+            SwingPhase::Recovery
+        }
+    }
+
+    // Seconds into the arc, held at either end outside the active frames
+    fn active_time(&self) -> f32 {
+        (self.swing_time - WIND_UP_TIME).clamp(0.0, SWING_TIME)
+    }
+
+    /// Angle of the racket above the line straight out from the shoulder, in radians.
+    /// Starts at `SWING_ARC` and falls to `-SWING_ARC` as the swing goes through.
+    pub fn contact_angle(&self) -> f32 {
+        let progress = self.active_time() / SWING_TIME;
+        SWING_ARC * (1.0 - 2.0 * progress)
+    }
+
+    /// Velocity of the racket head around the shoulder, positive y up. Zero outside
+    /// the active frames.
+    pub fn head_velocity(&self, transform: &Transform, size: &Size) -> Vec2 {
+        if self.phase() != SwingPhase::Active {
+            return Vec2::ZERO;
+        }
+        let angle = self.contact_angle();
+        let angular_speed = -2.0 * SWING_ARC / SWING_TIME;
+        let reach = size.0.x / 2.0 * self.reach;
+        Vec2::new(-facing(transform) * angle.sin(), angle.cos()) * reach * angular_speed
+    }
+
+    pub fn center(&self, transform: &Transform, size: &Size) -> Vec2 {
+        let angle = self.contact_angle();
+        let reach = size.0.x / 2.0 * self.reach;
+        transform.translation.truncate()
+            + Vec2::new(facing(transform) * angle.cos(), angle.sin()) * reach
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Shot {
+    Drive,
+    Lob,
+    Drop,
+    Smash,
+}
+
+impl Shot {
+    /// A swing in the air with the ball above the player's middle is a smash. On the
+    /// ground, holding lob or drop picks that shot and anything else drives.
+    fn choose(input: &PlayerInput, on_ground: bool, player_y: f32, ball_y: f32) -> Self {
+        if !on_ground && ball_y > player_y {
+            Shot::Smash
+        } else if input.lob {
+            Shot::Lob
+        } else if input.drop {
+            Shot::Drop
+        } else {
+            Shot::Drive
+        }
+    }
+
+    /// Launch velocity facing right, positive y up.
+    fn launch(self, racket: &Racket) -> Vec2 {
+        match self {
+            Shot::Drive => {
+                // Lower the launch angle the earlier in the arc the ball is met
+                let bottom_of_swing = racket.contact_angle() + SWING_ARC;
+                Vec2::from_angle(-bottom_of_swing * CONTACT_LOFT).rotate(RETURN_VELOCITY)
+            }
+            Shot::Lob => LOB_VELOCITY,
+            Shot::Drop => DROP_VELOCITY,
+            Shot::Smash => SMASH_VELOCITY,
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct BallHitEvent {
+    pub player: Entity,
+    pub ball: Entity,
+    /// From 0.0 off the edge of the racket late in the swing to 1.0 for the sweet spot.
+    pub quality: f32,
+}
+
+/// A racket contact once the hit is resolved, sent exactly once per contact. Anything
+/// that reacts to a hit should go off this, so they all land on the same tick.
+#[derive(Event)]
+pub struct HitConfirmed {
+    pub player: Entity,
+    pub quality: f32,
+    /// Speed the ball left the racket with.
+    pub speed: f32,
+    pub position: Vec2,
+}
+
+/// Returns made so far in the current point, serves not included.
+#[derive(Resource, Default)]
+pub struct Rally {
+    returns: u32,
+}
+
+impl Rally {
+    pub fn returns(&self) -> u32 {
+        self.returns
+    }
+
+    /// How many steps the speed cap has been raised by. Always 0 without the
+    /// escalation modifier.
+    fn escalation(&self, modifiers: &MatchModifiers) -> u32 {
+        if modifiers.escalation {
+            (self.returns / RETURNS_PER_ESCALATION).min(MAX_ESCALATION)
+        } else {
+            0
+        }
+    }
+
+    fn speed_cap(&self, match_config: &MatchConfig) -> f32 {
+        let escalation = self.escalation(&match_config.modifiers);
+        match_config.ball_speed_cap * (1.0 + ESCALATION_STEP * escalation as f32)
+    }
+}
+
+/// A return met in the sweet spot right as the swing started.
+#[derive(Event)]
+pub struct PerfectHit {
+    pub position: Vec2,
+}
+
+/// 1.0 when facing right, -1.0 when facing left.
+pub fn facing(transform: &Transform) -> f32 {
+    (transform.rotation * Vec3::X).x.signum()
+}
+
+/// Where the racket would be at the middle of a swing, at the player's front edge.
+pub fn racket_center(transform: &Transform, size: &Size) -> Vec2 {
+    transform.translation.truncate() + Vec2::new(facing(transform) * size.0.x / 2.0, 0.0)
+}
+
+pub fn swing_racket_system(
+    mut commands: Commands,
+    clock: Res<GameClock>,
+    mut query: Query<(Entity, &mut Racket)>,
+) {
+    for (entity, mut racket) in &mut query {
+        racket.swing_time += clock.delta();
+        if racket.swing_time >= WIND_UP_TIME + SWING_TIME + RECOVERY_TIME {
+            commands.entity(entity).remove::<Racket>();
+        }
+    }
+}
+
+type SwingingPlayerQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        &'static Size,
+        &'static Movement,
+        &'static PlayerInput,
+        &'static Racket,
+    ),
+    (With<Player>, Without<Ball>),
+>;
+
+type HitBallQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        &'static Size,
+        &'static mut Movement,
+        &'static mut Spin,
+        &'static mut Curve,
+        &'static BallState,
+    ),
+    With<Ball>,
+>;
+
+pub fn racket_hit_system(
+    match_config: Res<MatchConfig>,
+    mut rally: ResMut<Rally>,
+    player_query: SwingingPlayerQuery,
+    mut ball_query: HitBallQuery,
+    mut hit_events: EventWriter<BallHitEvent>,
+    mut perfect_events: EventWriter<PerfectHit>,
+) {
+    for (player, player_transform, player_size, player_movement, input, racket) in &player_query {
+        let (
+            ball,
+            ball_transform,
+            ball_size,
+            mut ball_movement,
+            mut ball_spin,
+            mut ball_curve,
+            ball_state,
+        ) = ball_query.single_mut();
+        if !ball_state.in_flight() || racket.phase() != SwingPhase::Active {
+            continue;
+        }
+        let direction = facing(player_transform);
+        // Only hit a ball that isn't already travelling away from the racket,
+        // otherwise it gets hit again on every tick it stays in reach
+        if ball_movement.velocity.x * direction > 0.0 {
+            continue;
+        }
+        let racket_center = racket.center(player_transform, player_size);
+        let collision = collide(
+            racket_center.extend(0.0),
+            Vec2::splat(racket.hitbox_size()),
+            ball_transform.translation,
+            ball_size.0,
+        );
+        if collision.is_none() {
+            continue;
+        }
+
+        // Brushing up the back of the ball puts topspin on it, chopping down on it
+        // slices it. Movement treats positive y as down, so flip the speeds.
+        let racket_rise =
+            racket.head_velocity(player_transform, player_size).y - player_movement.velocity.y;
+        let brush = racket_rise + ball_movement.velocity.y;
+        ball_spin.0 = -direction * brush * SPIN_TRANSFER / (ball_size.0.x / 2.0);
+
+        let shot = Shot::choose(
+            input,
+            player_movement.on_ground,
+            player_transform.translation.y,
+            ball_transform.translation.y,
+        );
+        // Off-center hits lose power and fly off the edge they were struck with,
+        // and a swing that has gone through takes pace off too
+        let offset = ball_transform.translation.truncate() - racket_center;
+        let edge = (racket.hitbox_size() + ball_size.0.x) / 2.0;
+        let sweet_spot = 1.0
+            - ((offset.length() - SWEET_SPOT_RADIUS).max(0.0) / (edge - SWEET_SPOT_RADIUS))
+                .min(1.0);
+        let progress = racket.active_time() / SWING_TIME;
+        let quality = sweet_spot * (1.0 - LATE_CONTACT_PENALTY * progress);
+        let power = MIN_HIT_POWER + (1.0 - MIN_HIT_POWER) * quality;
+        let error = (offset.y / edge).clamp(-1.0, 1.0) * (1.0 - sweet_spot) * MISHIT_ANGLE;
+        // Holding toward the net drives the return flatter and deeper, holding back
+        // floats it higher and shorter
+        let aim = (input.right as i8 - input.left as i8) as f32 * direction;
+        let launch = Vec2::from_angle(error - aim * AIM_ANGLE).rotate(shot.launch(racket))
+            * power
+            * (1.0 + aim * AIM_POWER);
+        // Serves get their own timing feedback
+        if sweet_spot >= 1.0
+            && racket.active_time() <= PERFECT_WINDOW
+            && *ball_state != BallState::Tossed
+        {
+            perfect_events.send(PerfectHit {
+                position: ball_transform.translation.truncate(),
+            });
+        }
+        // Movement treats positive y as down
+        let velocity = Vec2::new(
+            direction * launch.x + player_movement.velocity.x * RUN_CARRY,
+            -launch.y,
+        );
+        // Holding curve bends the flight down into the court, or up and away when
+        // aiming back. It is set by the hit alone, unlike spin the ball's speed and
+        // bounces don't change it, and the better the contact the more it bends.
+        let bend = match (input.curve, aim < 0.0) {
+            (false, _) => 0.0,
+            (true, false) => 1.0,
+            (true, true) => -1.0,
+        };
+        let below_flight = Vec2::new(direction * launch.y, launch.x).normalize_or_zero();
+        ball_curve.0 = below_flight * bend * racket.max_curve * quality;
+        if *ball_state != BallState::Tossed {
+            rally.returns += 1;
+        }
+        ball_movement.velocity = velocity.clamp_length_max(rally.speed_cap(&match_config));
+        ball_movement.velocity_remainder = Vec2::ZERO;
+        hit_events.send(BallHitEvent {
+            player,
+            ball,
+            quality,
+        });
+    }
+}
+
+pub fn reset_rally_system(mut rally: ResMut<Rally>, mut point_events: EventReader<PointWon>) {
+    if !point_events.is_empty() {
+        point_events.clear();
+        *rally = Rally::default();
+    }
+}
+
+// The ball goes from white to red as the rally escalates
+pub fn escalation_tint_system(
+    match_config: Res<MatchConfig>,
+    rally: Res<Rally>,
+    mut ball_query: Query<&mut Sprite, With<Ball>>,
+) {
+    if !rally.is_changed() {
+        return;
+    }
+    let heat = rally.escalation(&match_config.modifiers) as f32 / MAX_ESCALATION as f32;
+    ball_query.single_mut().color = Color::rgb(1.0, 1.0 - heat, 1.0 - heat);
+}
+
+pub fn perfect_hit_popup_system(
+    mut perfect_events: EventReader<PerfectHit>,
+    mut popup_events: EventWriter<SpawnPopup>,
+) {
+    for event in perfect_events.iter() {
+        popup_events.send(SpawnPopup {
+            text: "Perfect!".to_string(),
+            position: event.position,
+            color: Color::GOLD,
+        });
+    }
+}
+
+// A returned ball starts counting bounces afresh
+pub fn ball_hit_response_system(
+    mut events: EventReader<BallHitEvent>,
+    mut query: Query<(&mut Bounces, &mut BallState)>,
+) {
+    for event in events.iter() {
+        let (mut bounces, mut state) = query.get_mut(event.ball).unwrap();
+        *bounces = Bounces::default();
+        *state = BallState::InRally;
+    }
+}
+
+// Hits are only final once the response and the serve have had their say
+pub fn confirm_hit_system(
+    mut hit_events: EventReader<BallHitEvent>,
+    ball_query: Query<(&Transform, &Movement), With<Ball>>,
+    mut confirmed_events: EventWriter<HitConfirmed>,
+) {
+    for event in hit_events.iter() {
+        let Ok((transform, movement)) = ball_query.get(event.ball) else {
+            continue;
+        };
+        confirmed_events.send(HitConfirmed {
+            player: event.player,
+            quality: event.quality,
+            speed: movement.velocity.length(),
+            position: transform.translation.truncate(),
+        });
+    }
+}
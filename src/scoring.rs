@@ -0,0 +1,541 @@
+//! Tennis scoring. Points are awarded from ball collisions: a double bounce loses the
+//! point for that side, a first bounce past the baseline is out, and hitting the net
+//! loses the point for the side the ball was played from. A ball that clips the net
+//! cord plays on. What a point counts toward is up to the win condition, see the
+//! win_condition module, and the ball stays dead once the match is won.
+
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+
+use crate::{
+    ball_collision_response_system,
+    console::ConsoleAppExt,
+    court::{Court, CourtSide},
+    game_clock::{advance_game_clock_system, GameClock},
+    game_state::GameplaySet,
+    hud::{HudElement, HudItem},
+    line_calls::{line_call_system, BallOut},
+    racket::{Racket, Rally},
+    serve::{DoubleFault, Fault, ServeState, ServeVolleyed},
+    stamina::Stamina,
+    win_condition::{setup_win_condition_system, ActiveWinCondition, Decided},
+    Ball, BallState, Bounces, Dash, Dive, Jump, Movement, Net, Player, PlayerState,
+    SolidCollisionEvent, Spin, SwingBuffer, BALL_SPAWN, MAX_BALL_BOUNCES, PLAYER_SPAWN_X,
+};
+
+// Pause between a point being decided and the ball going back into play
+const POINT_OVER_TIME: f32 = 1.5;
+const GAMES_PER_SET: u8 = 6;
+// Best of three
+const SETS_TO_WIN: u8 = 2;
+
+#[derive(Event)]
+pub struct PointWon {
+    pub side: CourtSide,
+}
+
+#[derive(Event)]
+pub struct GameWon {
+    pub side: CourtSide,
+}
+
+#[derive(Event)]
+pub struct SetWon {
+    pub side: CourtSide,
+}
+
+#[derive(Event)]
+pub struct MatchWon {
+    pub side: CourtSide,
+}
+
+/// Throws away the match in progress and starts a new one from the first serve.
+#[derive(Event)]
+pub struct RestartMatch;
+
+#[derive(Resource, Default)]
+pub struct Score {
+    points: [u8; 2],
+}
+
+impl Score {
+    pub fn points(&self, side: CourtSide) -> u8 {
+        self.points[side.index()]
+    }
+
+    /// Awards a point to `side`, returning true if that wins the game.
+    /// Points start over from love after a game.
+    pub fn award(&mut self, side: CourtSide) -> bool {
+        self.points[side.index()] += 1;
+        let won = self.points(side) >= 4 && self.points(side) >= self.points(side.opposite()) + 2;
+        if won {
+            self.points = [0, 0];
+        }
+        won
+    }
+
+    /// The score as the umpire would call it, left player first.
+    pub fn call(&self) -> String {
+        let left = self.points(CourtSide::Left);
+        let right = self.points(CourtSide::Right);
+        if left >= 3 && right >= 3 {
+            return match left.cmp(&right) {
+                Ordering::Equal => "Deuce".to_string(),
+                Ordering::Greater => "Advantage Left".to_string(),
+                Ordering::Less => "Advantage Right".to_string(),
+            };
+        }
+        format!("{}-{}", point_name(left), point_name(right))
+    }
+}
+
+/// Games and sets won so far, and who serves the current game.
+#[derive(Resource)]
+pub struct MatchState {
+    games: [u8; 2],
+    sets: [u8; 2],
+    // Every point of the match, for win conditions that do not use games
+    points_won: [u32; 2],
+    // Gameplay seconds since the match started
+    elapsed: f32,
+    server: CourtSide,
+    winner: Option<CourtSide>,
+}
+
+impl Default for MatchState {
+    fn default() -> Self {
+        Self {
+            games: [0, 0],
+            sets: [0, 0],
+            points_won: [0, 0],
+            elapsed: 0.0,
+            server: CourtSide::Left,
+            winner: None,
+        }
+    }
+}
+
+impl MatchState {
+    pub fn games(&self, side: CourtSide) -> u8 {
+        self.games[side.index()]
+    }
+
+    pub fn sets(&self, side: CourtSide) -> u8 {
+        self.sets[side.index()]
+    }
+
+    pub fn points_won(&self, side: CourtSide) -> u32 {
+        self.points_won[side.index()]
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    pub fn server(&self) -> CourtSide {
+        self.server
+    }
+
+    pub fn pass_serve(&mut self) {
+        self.server = self.server.opposite();
+    }
+
+    /// Ends the match with `side` as the winner.
+    pub fn finish(&mut self, side: CourtSide) {
+        self.winner = Some(side);
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    /// Awards a game to `side` and passes the serve over, returning true if that wins
+    /// the set. Games start over after a set.
+    pub fn award_game(&mut self, side: CourtSide) -> bool {
+        self.games[side.index()] += 1;
+        self.pass_serve();
+        let won = self.games(side) >= GAMES_PER_SET
+            && self.games(side) >= self.games(side.opposite()) + 2;
+        if won {
+            self.games = [0, 0];
+        }
+        won
+    }
+
+    /// Awards a set to `side`, returning true if that wins the match.
+    pub fn award_set(&mut self, side: CourtSide) -> bool {
+        self.sets[side.index()] += 1;
+        if self.sets(side) >= SETS_TO_WIN {
+            self.finish(side);
+        }
+        self.is_over()
+    }
+
+    /// Sets and games, left player first.
+    pub fn summary(&self) -> String {
+        format!(
+            "Sets {}-{}  Games {}-{}  {:?} serves",
+            self.sets(CourtSide::Left),
+            self.sets(CourtSide::Right),
+            self.games(CourtSide::Left),
+            self.games(CourtSide::Right),
+            self.server(),
+        )
+    }
+}
+
+fn point_name(points: u8) -> &'static str {
+    match points {
+        0 => "0",
+        1 => "15",
+        2 => "30",
+        _ => "40",
+    }
+}
+
+// Seconds left until the next point starts, set while a point is over
+#[derive(Resource, Default)]
+pub struct PointOver(Option<f32>);
+
+impl PointOver {
+    pub fn is_over(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+#[derive(Component)]
+struct ScoreText;
+
+#[allow(clippy::too_many_arguments)]
+pub fn award_point_system(
+    court: Res<Court>,
+    win_condition: Res<ActiveWinCondition>,
+    mut score: ResMut<Score>,
+    mut match_state: ResMut<MatchState>,
+    mut point_over: ResMut<PointOver>,
+    serve_state: Res<ServeState>,
+    net_query: Query<(), With<Net>>,
+    ball_query: Query<(&Transform, &Bounces)>,
+    mut collision_events: EventReader<SolidCollisionEvent<Ball>>,
+    mut double_fault_events: EventReader<DoubleFault>,
+    mut volley_events: EventReader<ServeVolleyed>,
+    mut out_events: EventReader<BallOut>,
+    mut point_events: EventWriter<PointWon>,
+    mut game_events: EventWriter<GameWon>,
+    mut set_events: EventWriter<SetWon>,
+    mut match_events: EventWriter<MatchWon>,
+) {
+    // Serves are judged by the serve module, which reports double faults and serves
+    // the receiver hit before they bounced
+    let double_faults = double_fault_events
+        .iter()
+        .map(|event| event.server.opposite());
+    let volleyed_serves = volley_events.iter().map(|event| event.server);
+    let rally_winners = collision_events.iter().filter_map(|event| {
+        if *serve_state != ServeState::InPlay {
+            return None;
+        }
+        let (transform, bounces) = ball_query.get(event.collider).unwrap();
+        // A ball off the net cord plays on, only running into the net ends the point
+        let hit_net = event
+            .collided_x
+            .is_some_and(|solid| net_query.contains(solid));
+
+        if hit_net {
+            Some(court.side_of(transform.translation.x).opposite())
+        } else if event.collided_y.is_some() {
+            let side = court.side_of(transform.translation.x);
+            if bounces.count > MAX_BALL_BOUNCES {
+                Some(side.opposite())
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    });
+
+    // Out, the player who played it into this side loses
+    let outs = out_events.iter().map(|event| event.side);
+
+    let winners: Vec<CourtSide> = double_faults
+        .chain(volleyed_serves)
+        .chain(outs)
+        .chain(rally_winners)
+        .collect();
+    for side in winners {
+        if point_over.0.is_some() || match_state.is_over() {
+            continue;
+        }
+        point_over.0 = Some(POINT_OVER_TIME);
+        point_events.send(PointWon { side });
+        match_state.points_won[side.index()] += 1;
+        let decided = win_condition
+            .0
+            .point_won(side, &mut score, &mut match_state);
+        if decided >= Decided::Game {
+            game_events.send(GameWon { side });
+        }
+        if decided >= Decided::Set {
+            set_events.send(SetWon { side });
+        }
+        if decided == Decided::Match {
+            match_events.send(MatchWon { side });
+        }
+    }
+}
+
+fn match_clock_system(clock: Res<GameClock>, mut match_state: ResMut<MatchState>) {
+    if !match_state.is_over() {
+        match_state.elapsed += clock.delta();
+    }
+}
+
+pub fn next_point_system(
+    clock: Res<GameClock>,
+    match_state: Res<MatchState>,
+    mut point_over: ResMut<PointOver>,
+    mut serve_state: ResMut<ServeState>,
+    mut ball_query: Query<(&mut Transform, &mut Movement, &mut Bounces, &mut BallState)>,
+) {
+    // Once the match is over the ball stays dead where it landed
+    if match_state.is_over() {
+        return;
+    }
+    let Some(remaining) = point_over.0.as_mut() else {
+        return;
+    };
+    *remaining -= clock.delta();
+    if *remaining > 0.0 {
+        return;
+    }
+
+    point_over.0 = None;
+    let (mut transform, mut movement, mut bounces, mut state) = ball_query.single_mut();
+    transform.translation = BALL_SPAWN.extend(transform.translation.z);
+    transform.rotation = Quat::IDENTITY;
+    movement.velocity = Vec2::ZERO;
+    movement.velocity_remainder = Vec2::ZERO;
+    *bounces = Bounces::default();
+    *state = BallState::Held;
+    *serve_state = ServeState::Ready;
+}
+
+type RestartPlayerQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static CourtSide,
+        &'static mut Transform,
+        &'static mut Movement,
+        &'static mut PlayerState,
+        &'static mut Stamina,
+        &'static mut Jump,
+        &'static mut SwingBuffer,
+        &'static mut Dash,
+        &'static mut Dive,
+    ),
+    (With<Player>, Without<Ball>),
+>;
+
+#[allow(clippy::too_many_arguments)]
+fn restart_match_system(
+    mut commands: Commands,
+    mut restart_events: EventReader<RestartMatch>,
+    mut score: ResMut<Score>,
+    mut match_state: ResMut<MatchState>,
+    mut point_over: ResMut<PointOver>,
+    mut serve_state: ResMut<ServeState>,
+    mut rally: ResMut<Rally>,
+    mut ball_query: Query<
+        (
+            &mut Transform,
+            &mut Movement,
+            &mut Bounces,
+            &mut BallState,
+            &mut Spin,
+        ),
+        With<Ball>,
+    >,
+    mut player_query: RestartPlayerQuery,
+) {
+    if restart_events.iter().count() == 0 {
+        return;
+    }
+
+    *score = Score::default();
+    *match_state = MatchState::default();
+    *point_over = PointOver::default();
+    *serve_state = ServeState::Ready;
+    *rally = Rally::default();
+    let (mut transform, mut movement, mut bounces, mut state, mut spin) = ball_query.single_mut();
+    transform.translation = BALL_SPAWN.extend(transform.translation.z);
+    transform.rotation = Quat::IDENTITY;
+    movement.velocity = Vec2::ZERO;
+    movement.velocity_remainder = Vec2::ZERO;
+    *bounces = Bounces::default();
+    *state = BallState::Held;
+    spin.0 = 0.0;
+    // Back where they were spawned, facing the net, rested and with no swing, jump,
+    // dash or dive left over
+    for (
+        entity,
+        side,
+        mut transform,
+        mut movement,
+        mut state,
+        mut stamina,
+        mut jump,
+        mut swing_buffer,
+        mut dash,
+        mut dive,
+    ) in &mut player_query
+    {
+        let (x, rotation) = match side {
+            CourtSide::Left => (-PLAYER_SPAWN_X, Quat::default()),
+            CourtSide::Right => (PLAYER_SPAWN_X, Quat::from_rotation_y(std::f32::consts::PI)),
+        };
+        transform.translation.x = x;
+        transform.translation.y = 0.0;
+        transform.rotation = rotation;
+        movement.velocity = Vec2::ZERO;
+        movement.velocity_remainder = Vec2::ZERO;
+        *state = PlayerState::default();
+        *stamina = Stamina::default();
+        *jump = Jump::default();
+        *swing_buffer = SwingBuffer::default();
+        *dash = Dash::default();
+        *dive = Dive::default();
+        commands.entity(entity).remove::<Racket>();
+    }
+}
+
+fn setup_score_text_system(mut commands: Commands) {
+    commands.spawn((
+        ScoreText,
+        HudItem(HudElement::Score),
+        TextBundle::from_section(
+            "0-0",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(16.0),
+            ..default()
+        }),
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_score_text_system(
+    win_condition: Res<ActiveWinCondition>,
+    score: Res<Score>,
+    match_state: Res<MatchState>,
+    mut point_events: EventReader<PointWon>,
+    mut game_events: EventReader<GameWon>,
+    mut set_events: EventReader<SetWon>,
+    mut match_events: EventReader<MatchWon>,
+    mut fault_events: EventReader<Fault>,
+    mut double_fault_events: EventReader<DoubleFault>,
+    mut query: Query<&mut Text, With<ScoreText>>,
+) {
+    let summary = win_condition.0.summary(&score, &match_state);
+    for event in fault_events.iter() {
+        query.single_mut().sections[0].value =
+            format!("{summary}\nFault {:?}, second serve", event.server);
+    }
+    for event in double_fault_events.iter() {
+        query.single_mut().sections[0].value =
+            format!("{summary}\nDouble fault {:?}", event.server);
+    }
+    for event in point_events.iter() {
+        query.single_mut().sections[0].value = format!(
+            "{summary}\nPoint {:?}: {}",
+            event.side,
+            win_condition.0.call(&score, &match_state)
+        );
+    }
+    // A point that wins a game or set is called as that instead
+    for event in game_events.iter() {
+        query.single_mut().sections[0].value = format!("{summary}\nGame {:?}", event.side);
+    }
+    for event in set_events.iter() {
+        query.single_mut().sections[0].value = format!("{summary}\nSet {:?}", event.side);
+    }
+    for event in match_events.iter() {
+        query.single_mut().sections[0].value = format!("{summary}\nMatch {:?}", event.side);
+    }
+}
+
+// `score` shows the standing, `score <left> <right>` sets the points of the current
+// game and of the match, so both tennis and point count conditions pick them up
+fn score_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    match args {
+        [] => {}
+        [left, right] => {
+            let (Ok(left), Ok(right)) = (left.parse::<u8>(), right.parse::<u8>()) else {
+                return Err("usage: score [<left> <right>]".to_string());
+            };
+            world.resource_mut::<Score>().points = [left, right];
+            world.resource_mut::<MatchState>().points_won = [left.into(), right.into()];
+        }
+        _ => return Err("usage: score [<left> <right>]".to_string()),
+    }
+
+    let win_condition = &world.resource::<ActiveWinCondition>().0;
+    let score = world.resource::<Score>();
+    let match_state = world.resource::<MatchState>();
+    let text = format!(
+        "{}\n{}",
+        win_condition.summary(score, match_state),
+        win_condition.call(score, match_state)
+    );
+    if !args.is_empty() {
+        if let Ok(mut score_text) = world
+            .query_filtered::<&mut Text, With<ScoreText>>()
+            .get_single_mut(world)
+        {
+            score_text.sections[0].value = text.clone();
+        }
+    }
+    Ok(text)
+}
+
+pub struct ScoringPlugin;
+
+impl Plugin for ScoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .init_resource::<MatchState>()
+            .init_resource::<PointOver>()
+            .add_event::<PointWon>()
+            .add_event::<GameWon>()
+            .add_event::<SetWon>()
+            .add_event::<MatchWon>()
+            .add_event::<RestartMatch>()
+            .add_console_command("score", "score [<left> <right>]", score_command)
+            .add_systems(
+                Startup,
+                (setup_score_text_system, setup_win_condition_system),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    award_point_system
+                        .after(ball_collision_response_system)
+                        .after(line_call_system),
+                    next_point_system,
+                    match_clock_system.after(advance_game_clock_system),
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(Update, (restart_match_system, update_score_text_system));
+    }
+}
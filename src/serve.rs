@@ -0,0 +1,359 @@
+//! Serving. Each point starts with the ball held in front of the serving player, who
+//! tosses it with the swing button and then has to swing again to hit it. Meeting the
+//! ball at the top of the toss gives the fastest serve. Hit on the way up it loops
+//! high and slow, hit late it goes out flat.
+//!
+//! A serve has to clear the net and land in the receiver's service box. One that
+//! clips the net cord and still lands in is a let and is served again. The first
+//! fault gives the server a second serve, a double fault loses them the point.
+
+use bevy::prelude::*;
+
+use crate::{
+    ball_collision_response_system, ball_movement_system,
+    controller::PlayerInput,
+    court::{Court, CourtSide},
+    game_state::GameplaySet,
+    line_calls::line_call_system,
+    player_movement_system,
+    popup::SpawnPopup,
+    racket::{
+        ball_hit_response_system, confirm_hit_system, facing, racket_center, racket_hit_system,
+        BallHitEvent, Racket,
+    },
+    scoring::{award_point_system, MatchState, PointWon, RestartMatch},
+    Ball, BallState, Bounces, Movement, Net, Player, Size, SolidCollisionEvent, Spin,
+};
+
+// Straight up, enough to clear the player's head
+const TOSS_SPEED: f32 = 360.;
+// Gap between the top of the server's head and the held ball
+const TOSS_HEIGHT: f32 = 8.;
+const SERVE_SPEED: f32 = 560.;
+// Launch angle above horizontal for a serve hit at the top of the toss, in radians
+const SERVE_ANGLE: f32 = 0.45;
+// How much the launch angle changes between a hit at toss speed and one at the apex
+const SERVE_ANGLE_SPREAD: f32 = 0.4;
+// Fraction of SERVE_SPEED left for the worst timed serve
+const MIN_SERVE_POWER: f32 = 0.6;
+// Serves timed this close to the apex get a "Perfect!" popup
+const PERFECT_TIMING: f32 = 0.1;
+
+#[derive(Resource, Default, Clone, Copy, PartialEq, Debug)]
+pub enum ServeState {
+    // Ball held by the server until they toss it
+    #[default]
+    Ready,
+    // Ball in the air, with its vertical speed from the last tick. Positive is falling.
+    Tossed {
+        fall_speed: f32,
+    },
+    // Hit and on its way to the service box
+    Served,
+    // The serve landed in, the point plays out as a rally
+    InPlay,
+}
+
+#[derive(Event)]
+pub struct Fault {
+    pub server: CourtSide,
+}
+
+#[derive(Event)]
+pub struct DoubleFault {
+    pub server: CourtSide,
+}
+
+/// The receiver hit the serve before it bounced, which loses them the point.
+#[derive(Event)]
+pub struct ServeVolleyed {
+    pub server: CourtSide,
+}
+
+// Faults served so far this point
+#[derive(Resource, Default)]
+struct Faults(u8);
+
+// Set when the serve in the air has clipped the net cord
+#[derive(Resource, Default)]
+struct NetCordServe(bool);
+
+fn is_server(court: &Court, match_state: &MatchState, transform: &Transform) -> bool {
+    court.side_of(transform.translation.x) == match_state.server()
+}
+
+type ServerQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        &'static Size,
+        &'static PlayerInput,
+    ),
+    (With<Player>, Without<Ball>),
+>;
+type TossBallQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        &'static mut Movement,
+        &'static mut Spin,
+        &'static Size,
+        &'static mut BallState,
+    ),
+    With<Ball>,
+>;
+
+// Holds the ball in front of the server and tosses it when they press swing
+fn toss_system(
+    mut commands: Commands,
+    court: Res<Court>,
+    match_state: Res<MatchState>,
+    mut serve_state: ResMut<ServeState>,
+    player_query: ServerQuery,
+    mut ball_query: TossBallQuery,
+) {
+    if *serve_state != ServeState::Ready {
+        return;
+    }
+    let (mut ball_transform, mut ball_movement, mut ball_spin, ball_size, mut ball_state) =
+        ball_query.single_mut();
+    if *ball_state != BallState::Held {
+        return;
+    }
+    let Some((server, transform, size, input)) = player_query
+        .iter()
+        .find(|(_, transform, ..)| is_server(&court, &match_state, transform))
+    else {
+        return;
+    };
+
+    let hand = racket_center(transform, size)
+        + Vec2::new(0.0, (ball_size.0.y + size.0.y) / 2.0 + TOSS_HEIGHT);
+    ball_transform.translation = hand.extend(ball_transform.translation.z);
+    ball_movement.velocity = Vec2::ZERO;
+    ball_movement.velocity_remainder = Vec2::ZERO;
+    ball_spin.0 = 0.0;
+    ball_transform.rotation = Quat::IDENTITY;
+    if input.swing_pressed {
+        // Movement treats positive y as down
+        ball_movement.velocity.y = -TOSS_SPEED;
+        *ball_state = BallState::Tossed;
+        // The toss press would also start a swing, which would still be recovering
+        // by the time the ball comes down
+        commands.entity(server).remove::<Racket>();
+        *serve_state = ServeState::Tossed {
+            fall_speed: -TOSS_SPEED,
+        };
+    }
+}
+
+// Remembers how the toss was moving just before the racket could reach it
+fn track_toss_system(
+    mut serve_state: ResMut<ServeState>,
+    ball_query: Query<&Movement, With<Ball>>,
+) {
+    if let ServeState::Tossed { fall_speed } = serve_state.as_mut() {
+        *fall_speed = ball_query.single().velocity.y;
+    }
+}
+
+// A toss that lands unhit goes back to the server's hand
+fn dropped_toss_system(
+    mut serve_state: ResMut<ServeState>,
+    mut collision_events: EventReader<SolidCollisionEvent<Ball>>,
+    mut ball_query: Query<(&mut BallState, &mut Bounces)>,
+) {
+    for event in collision_events.iter() {
+        let (mut state, mut bounces) = ball_query.get_mut(event.collider).unwrap();
+        if *state == BallState::Tossed && event.collided_y.is_some() {
+            *state = BallState::Held;
+            *bounces = Bounces::default();
+            *serve_state = ServeState::Ready;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn serve_hit_system(
+    court: Res<Court>,
+    match_state: Res<MatchState>,
+    mut serve_state: ResMut<ServeState>,
+    mut hit_events: EventReader<BallHitEvent>,
+    player_query: Query<&Transform, With<Player>>,
+    mut ball_query: Query<(&Transform, &mut Movement, &mut BallState)>,
+    mut popup_events: EventWriter<SpawnPopup>,
+    mut volley_events: EventWriter<ServeVolleyed>,
+) {
+    let ServeState::Tossed { fall_speed } = *serve_state else {
+        if *serve_state == ServeState::Served {
+            for event in hit_events.iter() {
+                let hitter = player_query.get(event.player).unwrap();
+                if is_server(&court, &match_state, hitter) {
+                    // The server chasing down their own serve plays on
+                    *serve_state = ServeState::InPlay;
+                    continue;
+                }
+                // The receiver has to let the serve bounce. Scoring gives the server
+                // the point and the ball rolls out until the next one.
+                let (.., mut state) = ball_query.get_mut(event.ball).unwrap();
+                *state = BallState::Dead;
+                *serve_state = ServeState::InPlay;
+                volley_events.send(ServeVolleyed {
+                    server: match_state.server(),
+                });
+            }
+        }
+        hit_events.clear();
+        return;
+    };
+    for event in hit_events.iter() {
+        let (transform, mut movement, mut state) = ball_query.get_mut(event.ball).unwrap();
+        let direction = facing(player_query.get(event.player).unwrap());
+        // -1 when hit at toss speed on the way up, 0 at the apex, positive when falling
+        let timing = (fall_speed / TOSS_SPEED).clamp(-1.0, 1.0);
+        let power = MIN_SERVE_POWER + (1.0 - MIN_SERVE_POWER) * (1.0 - timing.abs());
+        let angle = SERVE_ANGLE - timing * SERVE_ANGLE_SPREAD;
+        let launch = Vec2::from_angle(angle) * SERVE_SPEED * power;
+        movement.velocity = Vec2::new(direction * launch.x, -launch.y);
+        *state = BallState::Served;
+        *serve_state = ServeState::Served;
+        if timing.abs() < PERFECT_TIMING {
+            popup_events.send(SpawnPopup {
+                text: "Perfect!".to_string(),
+                position: transform.translation.truncate(),
+                color: Color::GOLD,
+            });
+        }
+    }
+}
+
+// Judges where the serve first touches down. Runs before scoring, so a double fault
+// is awarded on the tick it happens.
+#[allow(clippy::too_many_arguments)]
+fn fault_system(
+    court: Res<Court>,
+    match_state: Res<MatchState>,
+    mut serve_state: ResMut<ServeState>,
+    mut faults: ResMut<Faults>,
+    mut net_cord: ResMut<NetCordServe>,
+    net_query: Query<(), With<Net>>,
+    mut ball_query: Query<(&Transform, &mut BallState, &mut Bounces)>,
+    mut collision_events: EventReader<SolidCollisionEvent<Ball>>,
+    mut fault_events: EventWriter<Fault>,
+    mut double_fault_events: EventWriter<DoubleFault>,
+    mut popup_events: EventWriter<SpawnPopup>,
+) {
+    if *serve_state != ServeState::Served {
+        net_cord.0 = false;
+    }
+    for event in collision_events.iter() {
+        if *serve_state != ServeState::Served {
+            continue;
+        }
+        let (transform, mut state, mut bounces) = ball_query.get_mut(event.collider).unwrap();
+        let is_net = |solid: Option<Entity>| solid.is_some_and(|solid| net_query.contains(solid));
+        let hit_net_face = is_net(event.collided_x);
+        if is_net(event.collided_y) {
+            net_cord.0 = true;
+        }
+        if !hit_net_face && (event.collided_y.is_none() || is_net(event.collided_y)) {
+            continue;
+        }
+
+        let server = match_state.server();
+        let (box_min, box_max) = court.service_box(server.opposite());
+        let x = transform.translation.x;
+        if !hit_net_face && (box_min..=box_max).contains(&x) {
+            if net_cord.0 {
+                // A let, the same serve is taken again
+                net_cord.0 = false;
+                popup_events.send(SpawnPopup {
+                    text: "Let".to_string(),
+                    position: transform.translation.truncate(),
+                    color: Color::WHITE,
+                });
+                *state = BallState::Held;
+                *bounces = Bounces::default();
+                *serve_state = ServeState::Ready;
+            } else {
+                *serve_state = ServeState::InPlay;
+            }
+            continue;
+        }
+
+        net_cord.0 = false;
+        faults.0 += 1;
+        if faults.0 < 2 {
+            fault_events.send(Fault { server });
+            *state = BallState::Held;
+            *bounces = Bounces::default();
+            *serve_state = ServeState::Ready;
+        } else {
+            // Scoring awards the point, the ball rolls out until the next one
+            double_fault_events.send(DoubleFault { server });
+            *state = BallState::Dead;
+            *serve_state = ServeState::InPlay;
+        }
+    }
+}
+
+fn reset_faults_system(mut faults: ResMut<Faults>, mut point_events: EventReader<PointWon>) {
+    if !point_events.is_empty() {
+        point_events.clear();
+        faults.0 = 0;
+    }
+}
+
+// A restarted match starts on a first serve, whatever the point it left off on had
+fn restart_serve_system(
+    mut faults: ResMut<Faults>,
+    mut net_cord: ResMut<NetCordServe>,
+    mut restart_events: EventReader<RestartMatch>,
+) {
+    if !restart_events.is_empty() {
+        restart_events.clear();
+        faults.0 = 0;
+        net_cord.0 = false;
+    }
+}
+
+pub struct ServePlugin;
+
+impl Plugin for ServePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ServeState>()
+            .init_resource::<Faults>()
+            .init_resource::<NetCordServe>()
+            .add_event::<Fault>()
+            .add_event::<DoubleFault>()
+            .add_event::<ServeVolleyed>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    toss_system
+                        .after(player_movement_system)
+                        .before(ball_movement_system),
+                    track_toss_system
+                        .after(ball_movement_system)
+                        .before(racket_hit_system),
+                    dropped_toss_system.after(ball_collision_response_system),
+                    serve_hit_system
+                        .after(ball_hit_response_system)
+                        .before(confirm_hit_system)
+                        .before(award_point_system),
+                    // Line calls and scoring skip serves, so they have to see one
+                    // before it is judged
+                    fault_system
+                        .after(ball_collision_response_system)
+                        .after(line_call_system)
+                        .before(award_point_system),
+                    reset_faults_system.after(award_point_system),
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(Update, restart_serve_system);
+    }
+}
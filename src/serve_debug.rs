@@ -0,0 +1,185 @@
+//! Debug view for the serve rules, toggled with F1. Draws both service boxes, the
+//! predicted flight of a served ball and the call the rules would make at the bounce point.
+//! The court is seen from the side, so there is no width and a serve can't go wide.
+
+use bevy::prelude::*;
+
+use crate::{
+    ai::BallQuery,
+    court::{Court, CourtSide},
+    match_config::MatchConfig,
+    scoring::MatchState,
+    trajectory::predict_ball_path,
+    Ball, BallState,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ServeCall {
+    In,
+    Long,
+    Net,
+}
+
+impl ServeCall {
+    fn label(self) -> &'static str {
+        match self {
+            ServeCall::In => "IN",
+            ServeCall::Long => "LONG",
+            ServeCall::Net => "NET",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            ServeCall::In => Color::GREEN,
+            ServeCall::Long => Color::ORANGE,
+            ServeCall::Net => Color::RED,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct ServeDebug {
+    enabled: bool,
+}
+
+#[derive(Component)]
+struct ServeCallLabel;
+
+/// Judges a serve hit from `server_side` given its predicted path.
+/// The ball has to clear the net and bounce inside the receiver's service box.
+fn call_serve(court: &Court, server_side: CourtSide, path: &[Vec2], half_height: f32) -> ServeCall {
+    for segment in path.windows(2) {
+        let (from, to) = (segment[0], segment[1]);
+        if court.side_of(from.x) == court.side_of(to.x) {
+            continue;
+        }
+        let t = (court.net_x - from.x) / (to.x - from.x);
+        let bottom_at_net = from.y + (to.y - from.y) * t - half_height;
+        if bottom_at_net < court.net_top() {
+            return ServeCall::Net;
+        }
+    }
+
+    let bounce = *path.last().unwrap();
+    let receiver_side = server_side.opposite();
+    if court.side_of(bounce.x) != receiver_side {
+        return ServeCall::Net;
+    }
+    let (min_x, max_x) = court.service_box(receiver_side);
+    if (min_x..=max_x).contains(&bounce.x) {
+        ServeCall::In
+    } else {
+        ServeCall::Long
+    }
+}
+
+fn setup_serve_debug_system(mut commands: Commands) {
+    commands.spawn((
+        ServeCallLabel,
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            ),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    ));
+}
+
+fn toggle_serve_debug_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut serve_debug: ResMut<ServeDebug>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F1) {
+        serve_debug.enabled = !serve_debug.enabled;
+    }
+}
+
+type LabelQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Text,
+        &'static mut Transform,
+        &'static mut Visibility,
+    ),
+    (With<ServeCallLabel>, Without<Ball>),
+>;
+
+fn serve_debug_system(
+    mut gizmos: Gizmos,
+    serve_debug: Res<ServeDebug>,
+    court: Res<Court>,
+    match_config: Res<MatchConfig>,
+    match_state: Res<MatchState>,
+    ball_query: BallQuery,
+    mut label_query: LabelQuery,
+) {
+    let (mut label_text, mut label_transform, mut label_visibility) = label_query.single_mut();
+    let (ball_transform, ball_movement, ball_spin, ball_curve, ball_size, ball_state) =
+        ball_query.single();
+    if !serve_debug.enabled || *ball_state != BallState::Served {
+        *label_visibility = Visibility::Hidden;
+        return;
+    }
+
+    let half_height = ball_size.0.y / 2.0;
+    let path = predict_ball_path(
+        ball_transform.translation.truncate(),
+        ball_movement.velocity,
+        ball_spin.0,
+        ball_curve.0,
+        half_height,
+        court.ground_y,
+        &match_config.modifiers,
+    );
+    let server_side = match_state.server();
+    let call = call_serve(&court, server_side, &path, half_height);
+
+    for side in [CourtSide::Left, CourtSide::Right] {
+        let (min_x, max_x) = court.service_box(side);
+        let color = if side == server_side {
+            Color::GRAY
+        } else {
+            call.color()
+        };
+        gizmos.rect_2d(
+            Vec2::new(
+                (min_x + max_x) / 2.0,
+                court.ground_y + court.net_height / 2.0,
+            ),
+            0.0,
+            Vec2::new(max_x - min_x, court.net_height),
+            color,
+        );
+    }
+    gizmos.line_2d(
+        Vec2::new(court.net_x, court.ground_y),
+        Vec2::new(court.net_x, court.net_top()),
+        Color::WHITE,
+    );
+    gizmos.linestrip_2d(path.iter().copied(), Color::YELLOW);
+
+    let bounce = *path.last().unwrap();
+    gizmos.circle_2d(bounce, 4.0, call.color());
+    label_text.sections[0].value = call.label().to_string();
+    label_text.sections[0].style.color = call.color();
+    label_transform.translation = (bounce + Vec2::new(0.0, 24.0)).extend(10.0);
+    *label_visibility = Visibility::Visible;
+}
+
+pub struct ServeDebugPlugin;
+
+impl Plugin for ServeDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ServeDebug>()
+            .add_systems(Startup, setup_serve_debug_system)
+            .add_systems(Update, toggle_serve_debug_system)
+            .add_systems(PostUpdate, serve_debug_system);
+    }
+}
@@ -0,0 +1,94 @@
+//! Shows a reticle where a falling ball is about to pass through the player's overhead
+//! reach, so the smash window can be read before it opens.
+
+use bevy::prelude::*;
+
+use crate::{
+    ai::{AiController, BallQuery},
+    console::{system_enabled, ConsoleAppExt},
+    court::Court,
+    match_config::MatchConfig,
+    trajectory::predict_ball_path,
+    Player, Size, RACKET_SIZE,
+};
+
+// Reach above the player's head, a racket length past either shoulder
+const SMASH_REACH: Vec2 = Vec2::new(RACKET_SIZE * 2.0, RACKET_SIZE * 2.0);
+// Half a second warning is enough to react without cluttering the whole flight
+const SMASH_LOOKAHEAD_TICKS: usize = 30;
+const RETICLE_RADIUS: f32 = 10.0;
+
+/// Sent every frame a smash reticle is showing.
+#[derive(Event)]
+pub struct SmashChance;
+
+type HumanQuery<'w, 's> =
+    Query<'w, 's, (&'static Transform, &'static Size), (With<Player>, Without<AiController>)>;
+
+fn smash_zone_system(
+    mut gizmos: Gizmos,
+    court: Res<Court>,
+    match_config: Res<MatchConfig>,
+    player_query: HumanQuery,
+    ball_query: BallQuery,
+    mut smash_events: EventWriter<SmashChance>,
+) {
+    let (ball_transform, ball_movement, ball_spin, ball_curve, ball_size, ball_state) =
+        ball_query.single();
+    // Positive y velocity means the ball is falling
+    if !ball_state.in_flight() || ball_movement.velocity.y <= 0.0 {
+        return;
+    }
+
+    let path = predict_ball_path(
+        ball_transform.translation.truncate(),
+        ball_movement.velocity,
+        ball_spin.0,
+        ball_curve.0,
+        ball_size.0.y / 2.0,
+        court.ground_y,
+        &match_config.modifiers,
+    );
+    for (player_transform, player_size) in &player_query {
+        let smash_zone = Rect::from_center_size(
+            player_transform.translation.truncate()
+                + Vec2::new(0.0, (player_size.0.y + SMASH_REACH.y) / 2.0),
+            Vec2::new(player_size.0.x, 0.0) + SMASH_REACH,
+        );
+        if let Some(target) = path
+            .iter()
+            .take(SMASH_LOOKAHEAD_TICKS)
+            .find(|position| smash_zone.contains(**position))
+        {
+            draw_reticle(&mut gizmos, *target);
+            smash_events.send(SmashChance);
+        }
+    }
+}
+
+fn draw_reticle(gizmos: &mut Gizmos, target: Vec2) {
+    gizmos.circle_2d(target, RETICLE_RADIUS, Color::GOLD);
+    gizmos.line_2d(
+        target - Vec2::X * RETICLE_RADIUS * 1.5,
+        target + Vec2::X * RETICLE_RADIUS * 1.5,
+        Color::GOLD,
+    );
+    gizmos.line_2d(
+        target - Vec2::Y * RETICLE_RADIUS * 1.5,
+        target + Vec2::Y * RETICLE_RADIUS * 1.5,
+        Color::GOLD,
+    );
+}
+
+pub struct SmashZonePlugin;
+
+impl Plugin for SmashZonePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SmashChance>()
+            .add_system_toggle("smash_zone", true)
+            .add_systems(
+                PostUpdate,
+                smash_zone_system.run_if(system_enabled("smash_zone")),
+            );
+    }
+}
@@ -0,0 +1,186 @@
+//! `--soak` plays AI against AI with no end, starting a new match as soon as one is
+//! won, and checks the physics every tick: no NaN or infinite positions and
+//! velocities, nothing outside the world, and no steady growth in the entity count.
+//! Violations are logged and written out with the entity states from the ticks
+//! leading up to them, to catch bugs that only show up hours into a run.
+
+use std::{collections::VecDeque, fmt::Write as _, fs, path::PathBuf};
+
+use bevy::{ecs::entity::Entities, prelude::*};
+
+use crate::{
+    ball_collision_response_system,
+    bug_report::write_tuning,
+    court::Court,
+    game_state::GameplaySet,
+    match_config::MatchConfig,
+    physics_guard::GuardTripped,
+    player_collision_response_system,
+    scoring::{award_point_system, MatchWon, RestartMatch},
+    Ball, Movement, Player,
+};
+
+const SOAK_REPORT_DIR: &str = "soak_reports";
+// Two seconds of entity states go into each report
+const HISTORY_TICKS: usize = 120;
+// How far past the baselines and above the ground anything may be before it is lost
+const WORLD_MARGIN: f32 = 400.0;
+// The entity count is taken as the baseline once the first match has settled in
+const WARMUP_TICKS: u64 = 600;
+const MAX_ENTITY_GROWTH: u32 = 50;
+// Ticks between written reports, a stuck NaN would otherwise write one every tick
+const REPORT_COOLDOWN_TICKS: u64 = 600;
+
+#[derive(Resource, Default)]
+struct Soak {
+    ticks: u64,
+    matches: u64,
+    violations: u64,
+    baseline_entities: Option<u32>,
+    last_report: Option<u64>,
+    history: VecDeque<String>,
+}
+
+pub fn soak_enabled(match_config: Res<MatchConfig>) -> bool {
+    match_config.soak
+}
+
+fn restart_won_match_system(
+    mut soak: ResMut<Soak>,
+    mut match_events: EventReader<MatchWon>,
+    mut restart_events: EventWriter<RestartMatch>,
+) {
+    for event in match_events.iter() {
+        soak.matches += 1;
+        info!(
+            "Soak match {} won by {:?} after {} ticks",
+            soak.matches, event.side, soak.ticks
+        );
+        restart_events.send(RestartMatch);
+    }
+}
+
+type InvariantQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        &'static Movement,
+        Option<&'static Player>,
+        Option<&'static Ball>,
+    ),
+>;
+
+fn check_invariants_system(
+    court: Res<Court>,
+    mut soak: ResMut<Soak>,
+    entities: &Entities,
+    mut tripped_events: EventReader<GuardTripped>,
+    query: InvariantQuery,
+) {
+    soak.ticks += 1;
+    let mut snapshot = format!("[tick {}]", soak.ticks);
+    let mut violations = Vec::new();
+    for (entity, transform, movement, player, ball) in &query {
+        let kind = if player.is_some() {
+            "player"
+        } else if ball.is_some() {
+            "ball"
+        } else {
+            "entity"
+        };
+        let position = transform.translation.truncate();
+        let _ = write!(
+            snapshot,
+            "\n{kind} {entity:?} translation = {position} velocity = {}",
+            movement.velocity
+        );
+
+        if !position.is_finite() || !movement.velocity.is_finite() {
+            violations.push(format!(
+                "{kind} {entity:?} has a non-finite position or velocity"
+            ));
+        } else if (position.x - court.net_x).abs() > court.baseline + WORLD_MARGIN
+            || position.y < court.ground_y - WORLD_MARGIN
+            || position.y > court.ground_y + WORLD_MARGIN * 2.0
+        {
+            violations.push(format!("{kind} {entity:?} left the world at {position}"));
+        }
+    }
+    // The guards already put these right, but they still count
+    violations.extend(tripped_events.iter().map(|event| event.problem.clone()));
+    soak.history.push_back(snapshot);
+    if soak.history.len() > HISTORY_TICKS {
+        soak.history.pop_front();
+    }
+
+    let entity_count = entities.len();
+    match soak.baseline_entities {
+        None if soak.ticks >= WARMUP_TICKS => soak.baseline_entities = Some(entity_count),
+        Some(baseline) if entity_count > baseline + MAX_ENTITY_GROWTH => {
+            violations.push(format!(
+                "entity count grew from {baseline} to {entity_count}"
+            ));
+            // Only report again if it keeps growing
+            soak.baseline_entities = Some(entity_count);
+        }
+        _ => {}
+    }
+
+    if violations.is_empty() {
+        return;
+    }
+    soak.violations += violations.len() as u64;
+    for violation in &violations {
+        error!("Soak violation at tick {}: {violation}", soak.ticks);
+    }
+    let cooling_down = soak
+        .last_report
+        .is_some_and(|tick| soak.ticks < tick + REPORT_COOLDOWN_TICKS);
+    if !cooling_down {
+        soak.last_report = Some(soak.ticks);
+        write_soak_report(&soak, &violations);
+    }
+}
+
+fn write_soak_report(soak: &Soak, violations: &[String]) {
+    let mut report = String::new();
+    let _ = writeln!(
+        report,
+        "[soak]\ntick = {}\nmatches = {}\nviolations = {}",
+        soak.ticks, soak.matches, soak.violations
+    );
+    for violation in violations {
+        let _ = writeln!(report, "violation = {violation}");
+    }
+    report.push('\n');
+    write_tuning(&mut report);
+    for snapshot in &soak.history {
+        let _ = writeln!(report, "\n{snapshot}");
+    }
+
+    let path = PathBuf::from(SOAK_REPORT_DIR).join(format!("violation-{}.txt", soak.ticks));
+    match fs::create_dir_all(SOAK_REPORT_DIR).and_then(|_| fs::write(&path, report)) {
+        Ok(()) => info!("Wrote soak report to {}", path.display()),
+        Err(error) => error!("Failed to write soak report: {error}"),
+    }
+}
+
+pub struct SoakPlugin;
+
+impl Plugin for SoakPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Soak>().add_systems(
+            FixedUpdate,
+            (
+                restart_won_match_system.after(award_point_system),
+                check_invariants_system
+                    .after(player_collision_response_system)
+                    .after(ball_collision_response_system),
+            )
+                .in_set(GameplaySet)
+                .run_if(soak_enabled),
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Running and jumping tire players out. Stamina drains while a player runs and with
+//! every jump, dash and dive, and only comes back between points. Once it is gone the player
+//! runs and jumps slower for the rest of the point. Each player's stamina shows as a bar in the
+//! bottom corner on their side.
+
+use bevy::prelude::*;
+
+use crate::{
+    game_clock::GameClock,
+    game_state::GameplaySet,
+    hud::{HudElement, HudItem},
+    serve::ServeState,
+    Ball, BallState, PlayerId,
+};
+
+// Stamina is 1.0 when full, these are fractions of that
+pub const RUN_DRAIN: f32 = 0.08;
+pub const JUMP_COST: f32 = 0.12;
+pub const DASH_COST: f32 = 0.2;
+pub const DIVE_COST: f32 = 0.25;
+const REGEN_RATE: f32 = 0.5;
+// Run and jump speed left to an exhausted player
+const EXHAUSTED_SPEED: f32 = 0.7;
+const BAR_SIZE: Vec2 = Vec2::new(80.0, 6.0);
+
+#[derive(Component)]
+pub struct Stamina(f32);
+
+impl Default for Stamina {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl Stamina {
+    /// From 1.0 when full down to 0.0.
+    pub fn level(&self) -> f32 {
+        self.0
+    }
+
+    pub fn drain(&mut self, amount: f32) {
+        self.0 = (self.0 - amount).max(0.0);
+    }
+
+    /// Multiplier on run and jump speed.
+    pub fn speed_scale(&self) -> f32 {
+        if self.0 <= 0.0 {
+            EXHAUSTED_SPEED
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Set by the `stamina` cheat. While it's on nothing drains stamina.
+#[derive(Resource, Default)]
+pub struct InfiniteStamina(pub bool);
+
+// The filled part of a player's stamina bar
+#[derive(Component)]
+struct StaminaBar(PlayerId);
+
+// Players get their breath back while waiting to serve and after the point is decided
+fn regenerate_stamina_system(
+    clock: Res<GameClock>,
+    serve_state: Res<ServeState>,
+    ball_query: Query<&BallState, With<Ball>>,
+    mut query: Query<&mut Stamina>,
+) {
+    let between_points =
+        *serve_state == ServeState::Ready || *ball_query.single() == BallState::Dead;
+    if !between_points {
+        return;
+    }
+    for mut stamina in &mut query {
+        stamina.0 = (stamina.0 + REGEN_RATE * clock.delta()).min(1.0);
+    }
+}
+
+fn setup_stamina_bars_system(mut commands: Commands) {
+    for id in [PlayerId(0), PlayerId(1)] {
+        let (left, right) = if id.0 == 0 {
+            (Val::Px(16.0), Val::Auto)
+        } else {
+            (Val::Auto, Val::Px(16.0))
+        };
+        commands
+            .spawn((
+                HudItem(HudElement::StaminaBars),
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        bottom: Val::Px(16.0),
+                        left,
+                        right,
+                        width: Val::Px(BAR_SIZE.x),
+                        height: Val::Px(BAR_SIZE.y),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.75).into(),
+                    ..default()
+                },
+            ))
+            .with_children(|bar| {
+                bar.spawn((
+                    StaminaBar(id),
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        background_color: Color::GREEN.into(),
+                        ..default()
+                    },
+                ));
+            });
+    }
+}
+
+fn update_stamina_bars_system(
+    player_query: Query<(&PlayerId, &Stamina)>,
+    mut bar_query: Query<(&StaminaBar, &mut Style, &mut BackgroundColor)>,
+) {
+    for (bar, mut style, mut color) in &mut bar_query {
+        let Some((_, stamina)) = player_query.iter().find(|(id, _)| **id == bar.0) else {
+            continue;
+        };
+        style.width = Val::Percent(stamina.0 * 100.0);
+        color.0 = if stamina.0 <= 0.0 {
+            Color::RED
+        } else {
+            Color::GREEN
+        };
+    }
+}
+
+pub struct StaminaPlugin;
+
+impl Plugin for StaminaPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InfiniteStamina>()
+            .add_systems(Startup, setup_stamina_bars_system)
+            .add_systems(FixedUpdate, regenerate_stamina_system.in_set(GameplaySet))
+            .add_systems(Update, update_stamina_bars_system);
+    }
+}
@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+
+use crate::{
+    approach, match_config::MatchModifiers, spin_acceleration, BALL_MASS, BALL_MAX_FALL_SPEED,
+    CURVE_DECAY, SPIN_DECAY, TIME_STEP,
+};
+
+// Three seconds of flight is more than any shot on screen needs.
+pub const MAX_PREDICTION_TICKS: usize = 180;
+
+/// Steps the ball integrator forward from `position` and `velocity` until the bottom
+/// of the ball reaches `ground_y`, returning every position along the way.
+/// The last point is the first bounce, unless the prediction ran out of ticks.
+/// `modifiers` must be the ones `ball_movement_system` uses, for gravity and wind, and
+/// `curve` is the push of a curve shot as the ball's `Curve` holds it.
+pub fn predict_ball_path(
+    mut position: Vec2,
+    mut velocity: Vec2,
+    mut spin: f32,
+    mut curve: Vec2,
+    half_height: f32,
+    ground_y: f32,
+    modifiers: &MatchModifiers,
+) -> Vec<Vec2> {
+    let mut path = vec![position];
+    for _ in 0..MAX_PREDICTION_TICKS {
+        if position.y - half_height <= ground_y {
+            break;
+        }
+        velocity.y = approach(
+            velocity.y,
+            BALL_MAX_FALL_SPEED,
+            BALL_MASS * modifiers.gravity_scale() * TIME_STEP,
+        );
+        velocity.x += modifiers.wind * TIME_STEP;
+        velocity += (spin_acceleration(velocity, spin) + curve) * TIME_STEP;
+        spin *= 1.0 - SPIN_DECAY * TIME_STEP;
+        curve *= 1.0 - CURVE_DECAY * TIME_STEP;
+        // Positive y velocity means falling, same as in collision_system
+        position += Vec2::new(velocity.x, -velocity.y) * TIME_STEP;
+        position.y = position.y.max(ground_y + half_height);
+        path.push(position);
+    }
+    path
+}
@@ -0,0 +1,169 @@
+//! How-to-play cards. The first time a mechanic comes up in a match a short card
+//! explains it, without pausing play. Cards that have been seen are remembered in the
+//! profile directory so each one only ever shows once.
+
+use std::{collections::HashSet, fs, path::PathBuf};
+
+use bevy::prelude::*;
+
+use crate::{
+    racket::BallHitEvent,
+    scoring::{MatchState, PointWon, Score},
+    smash_zone::SmashChance,
+    PROFILE_DIR,
+};
+
+const SEEN_CARDS_FILE: &str = "tutorial_seen.txt";
+const CARD_TIME: f32 = 5.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum TutorialCard {
+    FirstHit,
+    Smash,
+    BreakPoint,
+}
+
+impl TutorialCard {
+    fn key(self) -> &'static str {
+        match self {
+            TutorialCard::FirstHit => "first_hit",
+            TutorialCard::Smash => "smash",
+            TutorialCard::BreakPoint => "break_point",
+        }
+    }
+
+    fn text(self) -> &'static str {
+        match self {
+            TutorialCard::FirstHit => {
+                "Returns go higher the later in the swing you meet the ball. Swing early to drive it flat."
+            }
+            TutorialCard::Smash => {
+                "The gold reticle marks a ball dropping into reach overhead. Jump and swing to smash it."
+            }
+            TutorialCard::BreakPoint => {
+                "Break point! One more point and the receiver takes the serving player's game."
+            }
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct Tutorial {
+    seen: HashSet<String>,
+    queue: Vec<TutorialCard>,
+    // Seconds left on the card being shown
+    showing: Option<f32>,
+}
+
+impl Tutorial {
+    fn seen_cards_path() -> PathBuf {
+        PathBuf::from(PROFILE_DIR).join(SEEN_CARDS_FILE)
+    }
+
+    fn load() -> Self {
+        let seen = fs::read_to_string(Self::seen_cards_path())
+            .map(|contents| contents.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        Self { seen, ..default() }
+    }
+
+    fn trigger(&mut self, card: TutorialCard) {
+        if self.seen.insert(card.key().to_string()) {
+            self.queue.push(card);
+            let mut keys: Vec<&str> = self.seen.iter().map(String::as_str).collect();
+            keys.sort();
+            let result = fs::create_dir_all(PROFILE_DIR)
+                .and_then(|_| fs::write(Self::seen_cards_path(), keys.join("\n")));
+            if let Err(error) = result {
+                warn!("Failed to save tutorial progress: {error}");
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct TutorialCardText;
+
+fn setup_tutorial_card_system(mut commands: Commands) {
+    let mut card = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: 18.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        bottom: Val::Px(40.0),
+        left: Val::Px(16.0),
+        max_width: Val::Px(360.0),
+        padding: UiRect::all(Val::Px(8.0)),
+        ..default()
+    })
+    .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.75));
+    card.visibility = Visibility::Hidden;
+    commands.spawn((TutorialCardText, card));
+}
+
+fn trigger_tutorial_cards_system(
+    score: Res<Score>,
+    match_state: Res<MatchState>,
+    mut tutorial: ResMut<Tutorial>,
+    mut hit_events: EventReader<BallHitEvent>,
+    mut smash_events: EventReader<SmashChance>,
+    mut point_events: EventReader<PointWon>,
+) {
+    if !hit_events.is_empty() {
+        hit_events.clear();
+        tutorial.trigger(TutorialCard::FirstHit);
+    }
+    if !smash_events.is_empty() {
+        smash_events.clear();
+        tutorial.trigger(TutorialCard::Smash);
+    }
+    for _ in point_events.iter() {
+        let receiver = match_state.server().opposite();
+        let receiver_points = score.points(receiver);
+        if receiver_points >= 3 && receiver_points > score.points(match_state.server()) {
+            tutorial.trigger(TutorialCard::BreakPoint);
+        }
+    }
+}
+
+fn show_tutorial_card_system(
+    time: Res<Time>,
+    mut tutorial: ResMut<Tutorial>,
+    mut query: Query<(&mut Text, &mut Visibility), With<TutorialCardText>>,
+) {
+    let (mut text, mut visibility) = query.single_mut();
+    if let Some(remaining) = tutorial.showing.as_mut() {
+        *remaining -= time.delta_seconds();
+        if *remaining > 0.0 {
+            return;
+        }
+        tutorial.showing = None;
+        *visibility = Visibility::Hidden;
+    }
+    if tutorial.queue.is_empty() {
+        return;
+    }
+
+    let card = tutorial.queue.remove(0);
+    text.sections[0].value = card.text().to_string();
+    *visibility = Visibility::Visible;
+    tutorial.showing = Some(CARD_TIME);
+}
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Tutorial::load())
+            .add_systems(Startup, setup_tutorial_card_system)
+            .add_systems(
+                Update,
+                (trigger_tutorial_cards_system, show_tutorial_card_system).chain(),
+            );
+    }
+}
@@ -0,0 +1,216 @@
+//! How a match is won. Scoring decides who won each point and hands it to the
+//! `ActiveWinCondition`, which keeps the score and says whether the point also
+//! decided a game, a set or the match. Regular tennis is the default, `--first-to=<n>`,
+//! `--timed=<seconds>` and `--king-of-the-court` pick the other built in conditions,
+//! and a custom mode can insert its own `ActiveWinCondition` before startup to
+//! replace them.
+//!
+//! Conditions keep no state of their own, everything lives in `Score` and
+//! `MatchState`, so resetting those starts the condition over too.
+
+use bevy::prelude::*;
+
+use crate::{
+    court::CourtSide,
+    match_config::MatchConfig,
+    scoring::{MatchState, Score},
+};
+
+/// How much a point decided, each level including the ones before it.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Decided {
+    Point,
+    Game,
+    Set,
+    Match,
+}
+
+pub trait WinCondition: Send + Sync {
+    /// Scores a point won by `side`. The point is already counted in
+    /// `MatchState::points_won`, and a condition that ends the match calls
+    /// `MatchState::finish`.
+    fn point_won(
+        &self,
+        side: CourtSide,
+        score: &mut Score,
+        match_state: &mut MatchState,
+    ) -> Decided;
+
+    /// The standing shown above the latest call, left player first.
+    fn summary(&self, score: &Score, match_state: &MatchState) -> String;
+
+    /// The score called out after a point, left player first.
+    fn call(&self, score: &Score, match_state: &MatchState) -> String;
+}
+
+/// Points, games and best of three sets.
+pub struct Tennis;
+
+impl WinCondition for Tennis {
+    fn point_won(
+        &self,
+        side: CourtSide,
+        score: &mut Score,
+        match_state: &mut MatchState,
+    ) -> Decided {
+        if !score.award(side) {
+            Decided::Point
+        } else if !match_state.award_game(side) {
+            Decided::Game
+        } else if !match_state.award_set(side) {
+            Decided::Set
+        } else {
+            Decided::Match
+        }
+    }
+
+    fn summary(&self, _score: &Score, match_state: &MatchState) -> String {
+        match_state.summary()
+    }
+
+    fn call(&self, score: &Score, _match_state: &MatchState) -> String {
+        score.call()
+    }
+}
+
+/// The first side to win this many points wins the match. The serve changes
+/// hands every point.
+pub struct FirstTo(pub u32);
+
+impl WinCondition for FirstTo {
+    fn point_won(
+        &self,
+        side: CourtSide,
+        _score: &mut Score,
+        match_state: &mut MatchState,
+    ) -> Decided {
+        match_state.pass_serve();
+        if match_state.points_won(side) >= self.0 {
+            match_state.finish(side);
+            Decided::Match
+        } else {
+            Decided::Point
+        }
+    }
+
+    fn summary(&self, _score: &Score, match_state: &MatchState) -> String {
+        format!("First to {}  {:?} serves", self.0, match_state.server())
+    }
+
+    fn call(&self, _score: &Score, match_state: &MatchState) -> String {
+        points_call(match_state)
+    }
+}
+
+/// Whoever has won more points when this many seconds of play are up wins. A tie
+/// plays on until the next point decides it. The serve changes hands every point.
+pub struct Timed(pub f32);
+
+impl WinCondition for Timed {
+    fn point_won(
+        &self,
+        _side: CourtSide,
+        _score: &mut Score,
+        match_state: &mut MatchState,
+    ) -> Decided {
+        match_state.pass_serve();
+        if match_state.elapsed() < self.0 {
+            return Decided::Point;
+        }
+        let left = match_state.points_won(CourtSide::Left);
+        let right = match_state.points_won(CourtSide::Right);
+        if left == right {
+            return Decided::Point;
+        }
+        match_state.finish(if left > right {
+            CourtSide::Left
+        } else {
+            CourtSide::Right
+        });
+        Decided::Match
+    }
+
+    fn summary(&self, _score: &Score, match_state: &MatchState) -> String {
+        let left = (self.0 - match_state.elapsed()).max(0.0) as u32;
+        format!(
+            "{}:{:02} left  {:?} serves",
+            left / 60,
+            left % 60,
+            match_state.server()
+        )
+    }
+
+    fn call(&self, _score: &Score, match_state: &MatchState) -> String {
+        points_call(match_state)
+    }
+}
+
+/// Endless, see the king_of_the_court module. Whoever comes on for the loser of a
+/// point serves the next one.
+pub struct KingOfTheCourt;
+
+impl WinCondition for KingOfTheCourt {
+    fn point_won(
+        &self,
+        side: CourtSide,
+        _score: &mut Score,
+        match_state: &mut MatchState,
+    ) -> Decided {
+        if match_state.server() == side {
+            match_state.pass_serve();
+        }
+        Decided::Point
+    }
+
+    fn summary(&self, _score: &Score, _match_state: &MatchState) -> String {
+        "King of the court".to_string()
+    }
+
+    fn call(&self, _score: &Score, match_state: &MatchState) -> String {
+        format!("{:?} rotates out", match_state.server())
+    }
+}
+
+fn points_call(match_state: &MatchState) -> String {
+    format!(
+        "{}-{}",
+        match_state.points_won(CourtSide::Left),
+        match_state.points_won(CourtSide::Right)
+    )
+}
+
+/// The built in conditions, as picked from the command line.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum WinConditionKind {
+    #[default]
+    Tennis,
+    FirstTo(u32),
+    Timed(f32),
+    KingOfTheCourt,
+}
+
+impl WinConditionKind {
+    pub fn create(self) -> Box<dyn WinCondition> {
+        match self {
+            WinConditionKind::Tennis => Box::new(Tennis),
+            WinConditionKind::FirstTo(points) => Box::new(FirstTo(points)),
+            WinConditionKind::Timed(seconds) => Box::new(Timed(seconds)),
+            WinConditionKind::KingOfTheCourt => Box::new(KingOfTheCourt),
+        }
+    }
+}
+
+/// The condition the match is played to.
+#[derive(Resource)]
+pub struct ActiveWinCondition(pub Box<dyn WinCondition>);
+
+// A custom mode that inserted its own condition keeps it
+pub fn setup_win_condition_system(
+    mut commands: Commands,
+    match_config: Res<MatchConfig>,
+    active: Option<Res<ActiveWinCondition>>,
+) {
+    if active.is_none() {
+        commands.insert_resource(ActiveWinCondition(match_config.win_condition.create()));
+    }
+}